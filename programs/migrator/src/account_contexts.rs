@@ -7,7 +7,7 @@ use crate::{
 use anchor_lang::prelude::*;
 
 #[derive(Accounts)]
-#[instruction(bump: u8, name: String, description: String)]
+#[instruction(bump: u8, name: String, description: String, approvers: Vec<Pubkey>)]
 pub struct NewMigrator<'info> {
     /// [Migrator].
     #[account(
@@ -18,24 +18,75 @@ pub struct NewMigrator<'info> {
         ],
         bump = bump,
         payer = payer,
-        space = std::mem::size_of::<Migrator>() + name.as_bytes().len() + description.as_bytes().len()
+        space = Migrator::space(
+            approvers.len(),
+            &MigratorMetadata {
+                name: name.clone(),
+                description: description.clone(),
+                ..MigratorMetadata::default()
+            }
+        )
     )]
     pub migrator: Account<'info, Migrator>,
 
-    /// Account which will approve migrations.
-    pub approver: UncheckedAccount<'info>,
-
     /// Program ID.
     pub program: UncheckedAccount<'info>,
 
     /// Address where the program data will be stored.
     pub program_data: UncheckedAccount<'info>,
 
+    /// The [Reservation] of the program ID.
+    /// Only checked if the program has not yet been deployed.
+    pub reservation: UncheckedAccount<'info>,
+
+    /// Payer of transactions. For an undeployed program, must be
+    /// the reserver or the approver designated in the [Reservation].
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The [System] program.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for [migrator::adopt_program].
+#[derive(Accounts)]
+#[instruction(bump: u8, name: String, description: String, approvers: Vec<Pubkey>)]
+pub struct AdoptProgram<'info> {
+    /// [Migrator].
+    #[account(
+        init,
+        seeds = [
+            b"migrator".as_ref(),
+            program.program.key().to_bytes().as_ref()
+        ],
+        bump = bump,
+        payer = payer,
+        space = Migrator::space(
+            approvers.len(),
+            &MigratorMetadata {
+                name: name.clone(),
+                description: description.clone(),
+                ..MigratorMetadata::default()
+            }
+        )
+    )]
+    pub migrator: Account<'info, Migrator>,
+
+    /// The existing, live program.
+    pub program: LiveProgram<'info>,
+
+    /// The current upgrade authority of the program.
+    pub upgrade_authority: Signer<'info>,
+
     /// Payer of transactions.
+    #[account(mut)]
     pub payer: Signer<'info>,
 
     /// The [System] program.
     pub system_program: Program<'info, System>,
+
+    /// The [bpf_loader_upgradeable] program.
+    pub bpf_loader_upgradeable_program: Program<'info, BPFLoaderUpgradeable>,
 }
 
 #[derive(Accounts)]
@@ -76,39 +127,279 @@ pub struct UpgradeProgram<'info> {
 
 /// Accounts for [migrator::reserve_program_id].
 #[derive(Accounts)]
+#[instruction(bump: u8)]
 pub struct ReserveProgramID<'info> {
     /// Account containing the program ID.
+    #[account(mut)]
     pub program: Signer<'info>,
-    /// Payer to create the distributor.
+    /// The [Reservation].
+    #[account(
+        init,
+        seeds = [
+            b"reservation".as_ref(),
+            program.key().to_bytes().as_ref()
+        ],
+        bump = bump,
+        payer = payer
+    )]
+    pub reservation: Account<'info, Reservation>,
+    /// The account reserving the program ID.
+    pub reserver: Signer<'info>,
+    /// Payer to create the program account and [Reservation].
+    #[account(mut)]
     pub payer: Signer<'info>,
     /// The [System] program.
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
+#[instruction(bump: u8)]
 pub struct ApproveMigration<'info> {
     /// The migrator.
+    #[account(mut)]
     pub migrator: Account<'info, Migrator>,
     /// The migration.
+    #[account(mut)]
     pub migration: Account<'info, Migration>,
-    /// [Migrator::approver].
+    /// The [MigrationApproval] of the approver.
+    #[account(
+        init,
+        seeds = [
+            b"approval".as_ref(),
+            migration.key().to_bytes().as_ref(),
+            approver.key().to_bytes().as_ref()
+        ],
+        bump = bump,
+        payer = payer,
+        space = 8 + std::mem::size_of::<MigrationApproval>()
+    )]
+    pub approval: Account<'info, MigrationApproval>,
+    /// One of the [Migration::approvers].
     pub approver: Signer<'info>,
+    /// Payer of the [MigrationApproval].
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// The [System] program.
+    pub system_program: Program<'info, System>,
 }
 
+/// Accounts for [migrator::reject_migration].
+///
+/// When rejecting spam, the writable [Migrator::treasury] must also be
+/// passed in the remaining accounts to receive the bond.
 #[derive(Accounts)]
 pub struct RejectMigration<'info> {
     /// The migrator.
+    #[account(mut)]
     pub migrator: Account<'info, Migrator>,
     /// The migration.
+    #[account(mut)]
     pub migration: Account<'info, Migration>,
-    /// [Migrator::approver].
+    /// One of the [Migrator::approvers].
+    pub approver: Signer<'info>,
+}
+
+/// Accounts for [migrator::set_bond_config].
+#[derive(Accounts)]
+pub struct SetBondConfig<'info> {
+    /// The migrator.
+    #[account(mut)]
+    pub migrator: Account<'info, Migrator>,
+    /// One of the [Migrator::approvers].
     pub approver: Signer<'info>,
 }
 
+/// Accounts for [migrator::withdraw_lamports].
+#[derive(Accounts)]
+pub struct WithdrawLamports<'info> {
+    /// The migrator.
+    #[account(mut)]
+    pub migrator: Account<'info, Migrator>,
+    /// One of the [Migrator::approvers].
+    pub approver: Signer<'info>,
+    /// Account which receives the lamports.
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+}
+
+/// Accounts for [migrator::propose_eject].
+#[derive(Accounts)]
+pub struct ProposeEject<'info> {
+    /// The migrator.
+    #[account(mut)]
+    pub migrator: Account<'info, Migrator>,
+    /// One of the [Migrator::approvers].
+    pub approver: Signer<'info>,
+}
+
+/// Accounts for [migrator::cancel_eject].
+#[derive(Accounts)]
+pub struct CancelEject<'info> {
+    /// The migrator.
+    #[account(mut)]
+    pub migrator: Account<'info, Migrator>,
+    /// One of the [Migrator::approvers].
+    pub approver: Signer<'info>,
+}
+
+/// Accounts for [migrator::eject_migrator].
+#[derive(Accounts)]
+pub struct EjectMigrator<'info> {
+    /// The migrator, which is closed.
+    #[account(mut, close = recipient)]
+    pub migrator: Account<'info, Migrator>,
+    /// One of the [Migrator::approvers].
+    pub approver: Signer<'info>,
+    /// The live program.
+    pub program: LiveProgram<'info>,
+    /// [Migrator::eject_authority].
+    pub new_authority: UncheckedAccount<'info>,
+    /// Account which receives the lamports of the [Migrator].
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+    /// The [bpf_loader_upgradeable] program.
+    pub bpf_loader_upgradeable_program: Program<'info, BPFLoaderUpgradeable>,
+}
+
+/// Accounts for [migrator::set_guardian].
+#[derive(Accounts)]
+pub struct SetGuardian<'info> {
+    /// The migrator.
+    #[account(mut)]
+    pub migrator: Account<'info, Migrator>,
+    /// One of the [Migrator::approvers].
+    pub approver: Signer<'info>,
+}
+
+/// Accounts for [migrator::update_migrator_metadata].
+#[derive(Accounts)]
+pub struct UpdateMigratorMetadata<'info> {
+    /// The migrator.
+    #[account(mut)]
+    pub migrator: Account<'info, Migrator>,
+    /// One of the [Migrator::approvers].
+    pub approver: Signer<'info>,
+    /// Pays for any additional rent, and receives the rent freed by shrinking the [Migrator].
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// The [System] program.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for [migrator::veto_migration].
+#[derive(Accounts)]
+pub struct VetoMigration<'info> {
+    /// The migrator.
+    #[account(mut)]
+    pub migrator: Account<'info, Migrator>,
+    /// The [Migrator::pending_migration].
+    #[account(mut)]
+    pub migration: Account<'info, Migration>,
+    /// [Migrator::guardian].
+    pub guardian: Signer<'info>,
+}
+
+/// Accounts for [migrator::pause_migrator].
+#[derive(Accounts)]
+pub struct PauseMigrator<'info> {
+    /// The migrator.
+    #[account(mut)]
+    pub migrator: Account<'info, Migrator>,
+    /// [Migrator::guardian] or one of the [Migrator::approvers].
+    pub authority: Signer<'info>,
+}
+
+/// Accounts for [migrator::unpause_migrator].
+#[derive(Accounts)]
+pub struct UnpauseMigrator<'info> {
+    /// The migrator.
+    #[account(mut)]
+    pub migrator: Account<'info, Migrator>,
+    /// One of the [Migrator::approvers].
+    pub approver: Signer<'info>,
+}
+
+/// Accounts for [migrator::propose_new_approver].
+#[derive(Accounts)]
+pub struct ProposeNewApprover<'info> {
+    /// The migrator.
+    #[account(mut)]
+    pub migrator: Account<'info, Migrator>,
+    /// One of the [Migrator::approvers].
+    pub approver: Signer<'info>,
+}
+
+/// Accounts for [migrator::accept_approver].
+#[derive(Accounts)]
+#[instruction(bump: u8)]
+pub struct AcceptApprover<'info> {
+    /// The migrator.
+    #[account(mut)]
+    pub migrator: Account<'info, Migrator>,
+    /// The [ApproverRotation] recording this rotation.
+    #[account(
+        init,
+        seeds = [
+            b"rotation".as_ref(),
+            migrator.key().to_bytes().as_ref(),
+            migrator.num_rotations.to_le_bytes().as_ref()
+        ],
+        bump = bump,
+        payer = payer,
+        space = 8 + std::mem::size_of::<ApproverRotation>()
+    )]
+    pub rotation: Account<'info, ApproverRotation>,
+    /// [Migrator::proposed_approver].
+    pub new_approver: Signer<'info>,
+    /// Payer of the [ApproverRotation].
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// The [System] program.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for [migrator::finalize_program].
 #[derive(Accounts)]
-#[instruction(bump: u8, title: String, description: String)]
+pub struct FinalizeProgram<'info> {
+    /// The approved [Migration] and its [Migrator].
+    pub approved_action: ApprovedAction<'info>,
+
+    /// The existing, live program.
+    pub program: LiveProgram<'info>,
+
+    /// The [bpf_loader_upgradeable] program.
+    pub bpf_loader_upgradeable_program: Program<'info, BPFLoaderUpgradeable>,
+}
+
+/// Accounts for [migrator::transfer_authority].
+#[derive(Accounts)]
+pub struct TransferAuthority<'info> {
+    /// The approved [Migration] and its [Migrator].
+    pub approved_action: ApprovedAction<'info>,
+
+    /// The existing, live program.
+    pub program: LiveProgram<'info>,
+
+    /// The new upgrade authority of the program.
+    /// Must sign, unless it is another [Migrator] of the program, in which case
+    /// a quorum of its approvers must sign in the remaining accounts.
+    pub new_authority: UncheckedAccount<'info>,
+
+    /// The [bpf_loader_upgradeable] program.
+    pub bpf_loader_upgradeable_program: Program<'info, BPFLoaderUpgradeable>,
+}
+
+#[derive(Accounts)]
+#[instruction(
+    bump: u8,
+    title: String,
+    description: String,
+    kind: MigrationKind,
+    provenance: BuildProvenance
+)]
 pub struct ProposeMigration<'info> {
     /// The approved [Migration] and its [Migrator].
+    #[account(mut)]
     pub migrator: Account<'info, Migrator>,
     /// The approved [Migration] and its [Migrator].
     #[account(
@@ -119,17 +410,221 @@ pub struct ProposeMigration<'info> {
         ],
         bump = bump,
         payer = proposer,
-        space = std::mem::size_of::<Migration>() + title.as_bytes().len() + description.as_bytes().len()
+        space = std::mem::size_of::<Migration>()
+            + title.len()
+            + description.len()
+            + provenance.data_len()
+            + migrator.approvers.len() * std::mem::size_of::<Pubkey>()
     )]
     pub migration: Account<'info, Migration>,
-    /// The existing, live program.
-    pub buffer: Account<'info, UpgradeableLoaderAccount>,
+    /// The buffer to migrate to. Ignored if the [MigrationKind] does not use a buffer.
+    pub buffer: UncheckedAccount<'info>,
     /// The one proposing the migration. Also the payer.
+    #[account(mut)]
     pub proposer: Signer<'info>,
     /// The [System] program.
     pub system_program: Program<'info, System>,
 }
 
+/// Accounts for [migrator::withdraw_migration].
+#[derive(Accounts)]
+pub struct WithdrawMigration<'info> {
+    /// The [Migrator] of the [Migration].
+    #[account(mut)]
+    pub migrator: Account<'info, Migrator>,
+    /// The [Migration] to withdraw.
+    #[account(mut, close = proposer)]
+    pub migration: Account<'info, Migration>,
+    /// [Migration::buffer].
+    #[account(mut)]
+    pub buffer: UncheckedAccount<'info>,
+    /// [Migration::proposer].
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    /// The [bpf_loader_upgradeable] program.
+    pub bpf_loader_upgradeable_program: Program<'info, BPFLoaderUpgradeable>,
+}
+
+/// Accounts for [migrator::reclaim_buffer].
+#[derive(Accounts)]
+pub struct ReclaimBuffer<'info> {
+    /// The [Migrator] of the [Migration].
+    pub migrator: Account<'info, Migrator>,
+    /// The rejected, vetoed, or expired [Migration].
+    #[account(mut)]
+    pub migration: Account<'info, Migration>,
+    /// [Migration::buffer].
+    #[account(mut)]
+    pub buffer: Account<'info, UpgradeableLoaderAccount>,
+    /// [Migration::proposer].
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    /// The [bpf_loader_upgradeable] program.
+    pub bpf_loader_upgradeable_program: Program<'info, BPFLoaderUpgradeable>,
+}
+
+/// Accounts for [migrator::new_history_page].
+#[derive(Accounts)]
+#[instruction(bump: u8)]
+pub struct NewHistoryPage<'info> {
+    /// The [Migrator].
+    pub migrator: Account<'info, Migrator>,
+    /// The [MigrationHistory] page.
+    #[account(
+        init,
+        seeds = [
+            b"history".as_ref(),
+            migrator.key().to_bytes().as_ref(),
+            migrator.current_history_page().to_le_bytes().as_ref()
+        ],
+        bump = bump,
+        payer = payer,
+        space = 8
+            + std::mem::size_of::<MigrationHistory>()
+            + HISTORY_PAGE_SIZE * std::mem::size_of::<MigrationSummary>()
+    )]
+    pub history: Account<'info, MigrationHistory>,
+    /// Payer of the [MigrationHistory] page.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// The [System] program.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for [migrator::archive_migration].
+#[derive(Accounts)]
+pub struct ArchiveMigration<'info> {
+    /// The [Migrator] of the [Migration].
+    #[account(mut)]
+    pub migrator: Account<'info, Migrator>,
+    /// The finished [Migration].
+    #[account(mut, close = proposer)]
+    pub migration: Account<'info, Migration>,
+    /// The current [MigrationHistory] page of the [Migrator].
+    #[account(mut)]
+    pub history: Account<'info, MigrationHistory>,
+    /// [Migration::proposer], which receives the rent of the [Migration].
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+}
+
+/// Accounts for [migrator::reveal_description].
+#[derive(Accounts)]
+pub struct RevealDescription<'info> {
+    /// The executed [Migration].
+    #[account(mut)]
+    pub migration: Account<'info, Migration>,
+    /// Pays for the rent of the revealed description.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// The [System] program.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for [migrator::hash_buffer].
+#[derive(Accounts)]
+pub struct HashBuffer<'info> {
+    /// The [Migration] whose buffer is hashed.
+    #[account(mut)]
+    pub migration: Account<'info, Migration>,
+    /// The [Migration::buffer].
+    pub buffer: Account<'info, UpgradeableLoaderAccount>,
+}
+
+/// Accounts for [migrator::fund_bounty].
+#[derive(Accounts)]
+pub struct FundBounty<'info> {
+    /// The [Migration] to fund.
+    #[account(mut)]
+    pub migration: Account<'info, Migration>,
+    /// The [Migration::proposer], which funds the bounty.
+    /// Any unpaid bounty is returned to the proposer when the [Migration] is closed.
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    /// The [System] program.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for [migrator::new_migration_deposit].
+#[derive(Accounts)]
+#[instruction(bump: u8)]
+pub struct NewMigrationDeposit<'info> {
+    /// The [Migration] to fund.
+    pub migration: Account<'info, Migration>,
+    /// The [MigrationDeposit].
+    #[account(
+        init,
+        seeds = [
+            b"deposit".as_ref(),
+            migration.key().to_bytes().as_ref(),
+            contributor.key().to_bytes().as_ref()
+        ],
+        bump = bump,
+        payer = payer,
+        space = 8 + std::mem::size_of::<MigrationDeposit>()
+    )]
+    pub deposit: Account<'info, MigrationDeposit>,
+    /// The contributor of the deposit.
+    pub contributor: UncheckedAccount<'info>,
+    /// Payer of the [MigrationDeposit].
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// The [System] program.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for [migrator::fund_migration].
+#[derive(Accounts)]
+pub struct FundMigration<'info> {
+    /// The [Migration] to fund.
+    #[account(mut)]
+    pub migration: Account<'info, Migration>,
+    /// The contributor's [MigrationDeposit].
+    #[account(mut)]
+    pub deposit: Account<'info, MigrationDeposit>,
+    /// The [MigrationDeposit::contributor].
+    #[account(mut)]
+    pub contributor: Signer<'info>,
+    /// The [System] program.
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for [migrator::reclaim_deposit].
+#[derive(Accounts)]
+pub struct ReclaimDeposit<'info> {
+    /// The [Migrator] of the [Migration].
+    pub migrator: Account<'info, Migrator>,
+    /// The funded [Migration].
+    #[account(mut)]
+    pub migration: Account<'info, Migration>,
+    /// The contributor's [MigrationDeposit].
+    #[account(mut, close = contributor)]
+    pub deposit: Account<'info, MigrationDeposit>,
+    /// The [MigrationDeposit::contributor], which receives the refund.
+    /// Anyone may reclaim a deposit on behalf of its contributor, so that
+    /// outstanding deposits cannot keep a [Migration] open.
+    #[account(mut)]
+    pub contributor: UncheckedAccount<'info>,
+}
+
+/// Accounts for [migrator::release_program_id].
+#[derive(Accounts)]
+pub struct ReleaseProgramID<'info> {
+    /// Account containing the reserved program ID.
+    #[account(mut)]
+    pub program: UncheckedAccount<'info>,
+    /// The [Reservation] of the program ID.
+    #[account(mut, close = payer)]
+    pub reservation: Account<'info, Reservation>,
+    /// The [Reservation::reserver].
+    pub reserver: Signer<'info>,
+    /// The [Reservation::payer], which receives the rent back.
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    /// The canonical [Migrator] address of the program, which must not be in use.
+    pub migrator: UncheckedAccount<'info>,
+}
+
 //////////////////////////////////////////
 // Context structs
 //////////////////////////////////////////
@@ -146,19 +641,44 @@ pub struct UndeployedProgram<'info> {
 #[derive(Accounts)]
 pub struct LiveProgram<'info> {
     /// Account containing the program ID.
+    #[account(mut)]
     pub program: Account<'info, UpgradeableLoaderAccount>,
     /// Address where the program data will be stored.
+    #[account(mut)]
     pub program_data: Account<'info, UpgradeableLoaderAccount>,
 }
 
 #[derive(Accounts)]
 pub struct ApprovedMigration<'info> {
     /// The [Migrator] associated with the program to be deployed.
+    #[account(mut)]
     pub migrator: Account<'info, Migrator>,
     /// The [Migration] to deploy.
+    #[account(mut)]
     pub migration: Account<'info, Migration>,
     /// Account containing the buffer to deploy.
     pub buffer: Account<'info, UpgradeableLoaderAccount>,
-    /// Account which executed the deployment.
+    /// Account which executed the deployment. Receives the [Migration::bounty].
+    #[account(mut)]
+    pub executor: Signer<'info>,
+    /// [Migration::proposer], which receives its bond back.
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+}
+
+/// An approved [Migration] which does not use a buffer.
+#[derive(Accounts)]
+pub struct ApprovedAction<'info> {
+    /// The [Migrator] associated with the program.
+    #[account(mut)]
+    pub migrator: Account<'info, Migrator>,
+    /// The [Migration] to execute.
+    #[account(mut)]
+    pub migration: Account<'info, Migration>,
+    /// Account which executed the migration. Receives the [Migration::bounty].
+    #[account(mut)]
     pub executor: Signer<'info>,
+    /// [Migration::proposer], which receives its bond back.
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
 }
@@ -3,14 +3,53 @@ use solana_program::{
     bpf_loader_upgradeable::{self, UpgradeableLoaderState},
     system_program,
 };
-use vipers::{assert_keys, invariant, program_err, unwrap_opt, validate::Validate};
+use vipers::{assert_keys, invariant, program_err, try_or_err, unwrap_opt, validate::Validate};
 
-use crate::{
-    account_contexts::{NewMigrator, RejectMigration, ReserveProgramID},
-    bpf_loader_upgradeable::UpgradeableLoaderAccount,
-    ApproveMigration, ApprovedMigration, DeployProgram, LiveProgram, ProposeMigration,
-    UndeployedProgram, UpgradeProgram,
-};
+use crate::{account_contexts::*, bpf_loader_upgradeable::UpgradeableLoaderAccount, state::*};
+
+/// Accounts of an instruction which must be signed by a quorum of the [Migrator::approvers].
+pub trait ApproverQuorum<'info> {
+    /// The [Migrator] whose approvers must sign.
+    fn migrator(&self) -> &Migrator;
+    /// The approver signing the instruction.
+    fn approver(&self) -> AccountInfo<'info>;
+
+    /// Asserts that a quorum of approvers signed; see [Migrator::assert_approver_quorum].
+    fn assert_quorum(&self, remaining_accounts: &[AccountInfo]) -> ProgramResult {
+        self.migrator()
+            .assert_quorum_signed(&self.approver(), remaining_accounts)
+    }
+}
+
+/// Implements [ApproverQuorum] for accounts with `migrator` and `approver` fields.
+macro_rules! impl_approver_quorum {
+    ($($accounts:ident),* $(,)?) => {
+        $(
+            impl<'info> ApproverQuorum<'info> for $accounts<'info> {
+                fn migrator(&self) -> &Migrator {
+                    &self.migrator
+                }
+
+                fn approver(&self) -> AccountInfo<'info> {
+                    self.approver.to_account_info()
+                }
+            }
+        )*
+    };
+}
+
+impl_approver_quorum!(
+    RejectMigration,
+    SetBondConfig,
+    WithdrawLamports,
+    ProposeEject,
+    CancelEject,
+    EjectMigrator,
+    SetGuardian,
+    UpdateMigratorMetadata,
+    UnpauseMigrator,
+    ProposeNewApprover,
+);
 
 impl<'info> Validate<'info> for NewMigrator<'info> {
     fn validate(&self) -> ProgramResult {
@@ -20,6 +59,12 @@ impl<'info> Validate<'info> for NewMigrator<'info> {
 
         if program_data.data_is_empty() {
             // migrator for an undeployed program
+            let reservation: Account<Reservation> = Account::try_from(&self.reservation)?;
+            assert_keys!(reservation.program, *program, "reservation.program");
+            require!(
+                reservation.is_authorized(self.payer.key()),
+                UnauthorizedReserver
+            );
             (UndeployedProgram {
                 program: self.program.clone(),
                 program_data: self.program_data.clone(),
@@ -40,6 +85,14 @@ impl<'info> Validate<'info> for NewMigrator<'info> {
     }
 }
 
+impl<'info> Validate<'info> for AdoptProgram<'info> {
+    fn validate(&self) -> ProgramResult {
+        self.program
+            .validate_for_authority(self.upgrade_authority.key())?;
+        Ok(())
+    }
+}
+
 impl<'info> Validate<'info> for DeployProgram<'info> {
     fn validate(&self) -> ProgramResult {
         self.approved_migration.validate()?;
@@ -56,6 +109,69 @@ impl<'info> Validate<'info> for DeployProgram<'info> {
     }
 }
 
+impl<'info> Validate<'info> for FinalizeProgram<'info> {
+    fn validate(&self) -> ProgramResult {
+        self.approved_action.validate()?;
+        require!(
+            self.approved_action.migration.kind == MigrationKind::Finalize,
+            InvalidMigrationKind
+        );
+        self.program
+            .validate_for_migrator(self.approved_action.migrator.key())?;
+        assert_keys!(
+            self.approved_action.migrator.program_id,
+            self.program.program,
+            "approved_action.migrator.program_id"
+        );
+
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for TransferAuthority<'info> {
+    fn validate(&self) -> ProgramResult {
+        self.approved_action.validate()?;
+        match self.approved_action.migration.kind {
+            MigrationKind::TransferAuthority { new_authority } => {
+                assert_keys!(self.new_authority, new_authority, "new_authority");
+            }
+            _ => return program_err!(InvalidMigrationKind),
+        }
+        self.program
+            .validate_for_migrator(self.approved_action.migrator.key())?;
+        assert_keys!(
+            self.approved_action.migrator.program_id,
+            self.program.program,
+            "approved_action.migrator.program_id"
+        );
+
+        match self.receiving_migrator()? {
+            Some(receiving_migrator) => {
+                assert_keys!(
+                    receiving_migrator.program_id,
+                    self.program.program,
+                    "receiving_migrator.program_id"
+                );
+            }
+            None => {
+                require!(self.new_authority.is_signer, NewAuthorityNotSigner);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'info> TransferAuthority<'info> {
+    /// Loads the new authority as a [Migrator] if it is owned by this program.
+    pub fn receiving_migrator(&self) -> Result<Option<Account<'info, Migrator>>, ProgramError> {
+        if *self.new_authority.owner != crate::ID {
+            return Ok(None);
+        }
+        Ok(Some(Account::try_from(&self.new_authority)?))
+    }
+}
+
 impl<'info> Validate<'info> for UpgradeProgram<'info> {
     fn validate(&self) -> ProgramResult {
         self.approved_migration.validate()?;
@@ -73,13 +189,15 @@ impl<'info> Validate<'info> for UpgradeProgram<'info> {
 
 impl<'info> Validate<'info> for ApproveMigration<'info> {
     fn validate(&self) -> ProgramResult {
-        assert_keys!(
-            self.migration.migrator,
-            self.migration,
-            "migration.migrator"
+        assert_keys!(self.migration.migrator, self.migrator, "migration.migrator");
+        require!(!self.migrator.paused, MigratorPaused);
+        require!(
+            self.migration.is_approver(&self.approver.key()),
+            UnauthorizedApprover
         );
-        assert_keys!(self.migrator.approver, self.approver, "migrator.approver");
         require!(self.migration.executed_at == -1, MigrationAlreadyExecuted);
+        require!(self.migration.vetoed_at == -1, MigrationVetoed);
+        require!(self.migration.buffer_reclaimed_at == -1, BufferReclaimed);
 
         Ok(())
     }
@@ -87,83 +205,374 @@ impl<'info> Validate<'info> for ApproveMigration<'info> {
 
 impl<'info> Validate<'info> for RejectMigration<'info> {
     fn validate(&self) -> ProgramResult {
-        assert_keys!(
-            self.migration.migrator,
-            self.migration,
-            "migration.migrator"
-        );
-        assert_keys!(self.migrator.approver, self.approver, "migrator.approver");
+        assert_keys!(self.migration.migrator, self.migrator, "migration.migrator");
         require!(self.migration.executed_at == -1, MigrationAlreadyExecuted);
 
         Ok(())
     }
 }
 
-impl<'info> Validate<'info> for ProposeMigration<'info> {
+impl<'info> Validate<'info> for CancelEject<'info> {
     fn validate(&self) -> ProgramResult {
-        if let UpgradeableLoaderState::Buffer { authority_address } = **self.buffer {
-            assert_keys!(
-                unwrap_opt!(authority_address, "no buffer authority"),
-                self.migrator,
-                "buffer authority must be migrator"
-            );
-        } else {
-            return program_err!(BufferAuthorityMismatch);
-        }
+        require!(self.migrator.eject_after != -1, NoPendingEject);
         Ok(())
     }
 }
 
-impl<'info> Validate<'info> for ReserveProgramID<'info> {
+impl<'info> Validate<'info> for EjectMigrator<'info> {
     fn validate(&self) -> ProgramResult {
+        self.program.validate_for_migrator(self.migrator.key())?;
         assert_keys!(
-            *self.program.to_account_info().owner,
-            system_program::ID,
-            "program must not be a program account"
+            self.migrator.program_id,
+            self.program.program,
+            "migrator.program_id"
+        );
+
+        require!(self.migrator.eject_after != -1, NoPendingEject);
+        // the migrator holds the bonds, funding and buffers of open migrations
+        require!(self.migrator.num_open_migrations == 0, MigrationsOpen);
+        assert_keys!(
+            self.migrator.eject_authority,
+            self.new_authority,
+            "migrator.eject_authority"
+        );
+        require!(
+            Clock::get()?.unix_timestamp >= self.migrator.eject_after,
+            EjectTimelocked
         );
         Ok(())
     }
 }
 
-impl<'info> Validate<'info> for ApprovedMigration<'info> {
+impl<'info> Validate<'info> for VetoMigration<'info> {
     fn validate(&self) -> ProgramResult {
+        require!(
+            self.migrator.guardian != Pubkey::default(),
+            UnauthorizedGuardian
+        );
+        assert_keys!(self.migrator.guardian, self.guardian, "migrator.guardian");
+        assert_keys!(self.migration.migrator, self.migrator, "migration.migrator");
         assert_keys!(
             self.migrator.pending_migration,
             self.migration,
             "migrator.pending_migration"
         );
+        require!(
+            Clock::get()?.unix_timestamp < self.migrator.approval_expires_at,
+            MigrationWindowExpired
+        );
+        require!(self.migration.executed_at == -1, MigrationAlreadyExecuted);
+
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for PauseMigrator<'info> {
+    fn validate(&self) -> ProgramResult {
+        let authority = self.authority.key();
+        require!(
+            self.migrator.is_approver(&authority)
+                || (self.migrator.guardian != Pubkey::default()
+                    && self.migrator.guardian == authority),
+            UnauthorizedPauser
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for AcceptApprover<'info> {
+    fn validate(&self) -> ProgramResult {
+        require!(
+            self.migrator.proposed_approver != Pubkey::default(),
+            NoPendingRotation
+        );
+        assert_keys!(
+            self.migrator.proposed_approver,
+            self.new_approver,
+            "migrator.proposed_approver"
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for ProposeMigration<'info> {
+    fn validate(&self) -> ProgramResult {
+        require!(!self.migrator.paused, MigratorPaused);
+        Ok(())
+    }
+}
+
+impl<'info> ProposeMigration<'info> {
+    /// Validates that the buffer is held by the [Migrator].
+    pub fn validate_buffer(&self) -> ProgramResult {
+        let buffer: Account<UpgradeableLoaderAccount> = Account::try_from(&self.buffer)?;
+        validate_buffer_authority(&buffer, self.migrator.key())
+    }
+}
+
+impl<'info> Validate<'info> for WithdrawMigration<'info> {
+    fn validate(&self) -> ProgramResult {
         assert_keys!(self.migration.migrator, self.migrator, "migration.migrator");
+        assert_keys!(self.migration.proposer, self.proposer, "migration.proposer");
         assert_keys!(self.migration.buffer, self.buffer, "migration.buffer");
+        require!(self.migration.executed_at == -1, MigrationAlreadyExecuted);
+        require!(
+            !self
+                .migrator
+                .is_pending(self.migration.key(), Clock::get()?.unix_timestamp),
+            MigrationPending
+        );
+        // anyone may refund outstanding deposits with [crate::migrator::reclaim_deposit]
+        require!(self.migration.funding == 0, MigrationHasDeposits);
+        Ok(())
+    }
+}
 
-        if let UpgradeableLoaderState::Buffer { authority_address } = **self.buffer {
-            assert_keys!(
-                unwrap_opt!(authority_address, "no buffer authority"),
-                self.migrator,
-                "buffer authority must be migrator"
-            );
-        } else {
-            return program_err!(BufferAuthorityMismatch);
+impl<'info> Validate<'info> for ReclaimBuffer<'info> {
+    fn validate(&self) -> ProgramResult {
+        assert_keys!(self.migration.migrator, self.migrator, "migration.migrator");
+        assert_keys!(self.migration.proposer, self.proposer, "migration.proposer");
+        assert_keys!(self.migration.buffer, self.buffer, "migration.buffer");
+
+        let migration = &self.migration;
+        require!(migration.executed_at == -1, MigrationAlreadyExecuted);
+        require!(migration.buffer_reclaimed_at == -1, BufferReclaimed);
+        require!(
+            migration.rejected_at != -1
+                || migration.vetoed_at != -1
+                || migration.is_expired(Clock::get()?.unix_timestamp),
+            MigrationNotFinished
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for NewHistoryPage<'info> {
+    fn validate(&self) -> ProgramResult {
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for ArchiveMigration<'info> {
+    fn validate(&self) -> ProgramResult {
+        assert_keys!(self.migration.migrator, self.migrator, "migration.migrator");
+        assert_keys!(self.migration.proposer, self.proposer, "migration.proposer");
+        assert_keys!(self.history.migrator, self.migrator, "history.migrator");
+        require!(
+            self.history.page == self.migrator.current_history_page(),
+            HistoryPageMismatch
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(self.migration.outcome(now).is_some(), MigrationNotFinished);
+        require!(
+            !self.migrator.is_pending(self.migration.key(), now),
+            MigrationPending
+        );
+        // anyone may refund outstanding deposits with [crate::migrator::reclaim_deposit]
+        require!(self.migration.funding == 0, MigrationHasDeposits);
+        // the proposer needs the migration to reclaim a buffer still held by the migrator
+        if self.migration.has_buffer() && self.migration.executed_at == -1 {
+            require!(self.migration.buffer_reclaimed_at != -1, BufferNotReclaimed);
         }
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for RevealDescription<'info> {
+    fn validate(&self) -> ProgramResult {
+        require!(self.migration.executed_at != -1, MigrationNotExecuted);
+        require!(self.migration.is_description_sealed(), DescriptionNotSealed);
+        Ok(())
+    }
+}
 
+impl<'info> Validate<'info> for HashBuffer<'info> {
+    fn validate(&self) -> ProgramResult {
+        require!(self.migration.has_buffer(), InvalidMigrationKind);
         assert_keys!(self.migration.buffer, self.buffer, "migration.buffer");
+        require!(self.migration.buffer_hashed_at == -1, BufferAlreadyHashed);
+        require!(self.migration.buffer_reclaimed_at == -1, BufferReclaimed);
+        Ok(())
+    }
+}
 
+impl<'info> Validate<'info> for FundBounty<'info> {
+    fn validate(&self) -> ProgramResult {
+        assert_keys!(self.migration.proposer, self.proposer, "migration.proposer");
+        require!(self.migration.executed_at == -1, MigrationAlreadyExecuted);
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for NewMigrationDeposit<'info> {
+    fn validate(&self) -> ProgramResult {
+        require!(self.migration.executed_at == -1, MigrationAlreadyExecuted);
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for FundMigration<'info> {
+    fn validate(&self) -> ProgramResult {
+        assert_keys!(self.deposit.migration, self.migration, "deposit.migration");
         assert_keys!(
-            self.migrator.pending_migration,
-            self.migration,
-            "pending_migration"
+            self.deposit.contributor,
+            self.contributor,
+            "deposit.contributor"
         );
-        let migrator = &self.migrator;
-        let now = Clock::get()?.unix_timestamp;
-        require!(migrator.approval_expires_at != -1, NoApprovedMigration);
-        require!(migrator.approval_expires_at < now, MigrationWindowExpired);
-
-        require!(self.migration.rejected_at != -1, MigrationRejected);
         require!(self.migration.executed_at == -1, MigrationAlreadyExecuted);
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for ReclaimDeposit<'info> {
+    fn validate(&self) -> ProgramResult {
+        assert_keys!(self.migration.migrator, self.migrator, "migration.migrator");
+        assert_keys!(self.deposit.migration, self.migration, "deposit.migration");
+        assert_keys!(
+            self.deposit.contributor,
+            self.contributor,
+            "deposit.contributor"
+        );
 
+        // deposits are locked while the migration may be executed
+        require!(
+            self.migration.executed_at != -1
+                || !self
+                    .migrator
+                    .is_pending(self.migration.key(), Clock::get()?.unix_timestamp),
+            DepositLocked
+        );
         Ok(())
     }
 }
 
+impl<'info> Validate<'info> for ReleaseProgramID<'info> {
+    fn validate(&self) -> ProgramResult {
+        assert_keys!(
+            self.reservation.program,
+            self.program,
+            "reservation.program"
+        );
+        assert_keys!(
+            self.reservation.reserver,
+            self.reserver,
+            "reservation.reserver"
+        );
+        assert_keys!(self.reservation.payer, self.payer, "reservation.payer");
+        assert_keys!(
+            *self.program.owner,
+            crate::ID,
+            "program must be owned by this program"
+        );
+
+        let (migrator_address, _) = Pubkey::find_program_address(
+            &[b"migrator".as_ref(), &self.program.key().to_bytes()],
+            &crate::ID,
+        );
+        assert_keys!(
+            self.migrator,
+            migrator_address,
+            "migrator should be canonical"
+        );
+        // a migrator would be left managing a program ID which no longer exists
+        require!(self.migrator.data_is_empty(), MigratorExists);
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for ReserveProgramID<'info> {
+    fn validate(&self) -> ProgramResult {
+        assert_keys!(
+            *self.program.to_account_info().owner,
+            system_program::ID,
+            "program must not be a program account"
+        );
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for ApprovedMigration<'info> {
+    fn validate(&self) -> ProgramResult {
+        validate_approved(&self.migrator, &self.migration, &self.proposer)?;
+        validate_buffer_authority(&self.buffer, self.migrator.key())?;
+
+        let buffer_info = self.buffer.to_account_info();
+        let data_offset = try_or_err!(UpgradeableLoaderState::buffer_data_offset(), ParseError);
+        require!(
+            buffer_info.data_len() == data_offset + self.migration.approved_buffer_len as usize,
+            BufferHashMismatch
+        );
+
+        match self.migration.kind {
+            MigrationKind::Upgrade => {
+                assert_keys!(self.migration.buffer, self.buffer, "migration.buffer");
+                // the buffer must still be exactly what the approvers approved
+                require!(self.migration.buffer_hashed_at != -1, BufferNotHashed);
+                require!(self.migration.buffer_matches_approval(), BufferHashMismatch);
+            }
+            MigrationKind::EmbargoedUpgrade { buffer_hash, .. } => {
+                // the buffer is only revealed now, so it is hashed in full
+                let data = buffer_info.try_borrow_data()?;
+                let hash = solana_program::hash::hash(&data[data_offset..]);
+                require!(hash.to_bytes() == buffer_hash, BufferHashMismatch);
+            }
+            _ => return program_err!(InvalidMigrationKind),
+        }
+
+        Ok(())
+    }
+}
+
+impl<'info> Validate<'info> for ApprovedAction<'info> {
+    fn validate(&self) -> ProgramResult {
+        validate_approved(&self.migrator, &self.migration, &self.proposer)
+    }
+}
+
+/// Validates that a [Migration] is the approved [Migrator::pending_migration] and may be executed now.
+fn validate_approved(
+    migrator: &Account<Migrator>,
+    migration: &Account<Migration>,
+    proposer: &AccountInfo,
+) -> ProgramResult {
+    require!(!migrator.paused, MigratorPaused);
+    assert_keys!(
+        migrator.pending_migration,
+        *migration,
+        "migrator.pending_migration"
+    );
+    assert_keys!(migration.migrator, *migrator, "migration.migrator");
+    assert_keys!(migration.proposer, *proposer, "migration.proposer");
+
+    let now = Clock::get()?.unix_timestamp;
+    require!(migrator.approval_expires_at != -1, NoApprovedMigration);
+    require!(now < migrator.approval_expires_at, MigrationWindowExpired);
+    require!(now >= migrator.executable_after, MigrationTimelocked);
+
+    require!(migration.has_quorum(), QuorumNotReached);
+    require!(migration.rejected_at == -1, MigrationRejected);
+    require!(migration.vetoed_at == -1, MigrationVetoed);
+    require!(migration.executed_at == -1, MigrationAlreadyExecuted);
+    require!(migration.buffer_reclaimed_at == -1, BufferReclaimed);
+
+    Ok(())
+}
+
+/// Validates that the authority of a buffer is the [Migrator].
+fn validate_buffer_authority(buffer: &UpgradeableLoaderAccount, migrator: Pubkey) -> ProgramResult {
+    if let UpgradeableLoaderState::Buffer { authority_address } = **buffer {
+        assert_keys!(
+            unwrap_opt!(authority_address, "no buffer authority"),
+            migrator,
+            "buffer authority must be migrator"
+        );
+    } else {
+        return program_err!(BufferAuthorityMismatch);
+    }
+    Ok(())
+}
+
 impl<'info> UndeployedProgram<'info> {
     pub fn validate_for_migrator(&self, migrator: Pubkey) -> ProgramResult {
         let program = &self.program;
@@ -196,6 +605,11 @@ impl<'info> UndeployedProgram<'info> {
 
 impl<'info> LiveProgram<'info> {
     pub fn validate_for_migrator(&self, migrator: Pubkey) -> ProgramResult {
+        self.validate_for_authority(migrator)
+    }
+
+    /// Checks that `authority` is the upgrade authority of the program.
+    pub fn validate_for_authority(&self, authority: Pubkey) -> ProgramResult {
         let program = &self.program;
         let program_data = &self.program_data;
 
@@ -212,8 +626,8 @@ impl<'info> LiveProgram<'info> {
             assert_keys!(programdata_address, *program_data, "programdata_address");
             assert_keys!(
                 unwrap_opt!(upgrade_authority_address, "upgrade_authority must be set"),
-                migrator,
-                "upgrade_authority must be migrator"
+                authority,
+                "upgrade_authority"
             );
         } else {
             return program_err!(ParseError);
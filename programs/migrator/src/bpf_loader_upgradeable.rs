@@ -1,8 +1,11 @@
 use anchor_lang::{
-    prelude::{ProgramError, Pubkey},
+    prelude::{AccountMeta, ProgramError, Pubkey},
     Id, Owner,
 };
-use solana_program::{bpf_loader_upgradeable, declare_id, instruction::InstructionError};
+use solana_program::{
+    bpf_loader_upgradeable, declare_id,
+    instruction::{Instruction, InstructionError},
+};
 use std::ops::Deref;
 use vipers::try_or_err;
 
@@ -21,6 +24,29 @@ impl anchor_lang::AccountDeserialize for BPFLoaderUpgradeable {
     }
 }
 
+/// Index of the `SetAuthorityChecked` variant of the loader's `UpgradeableLoaderInstruction`,
+/// which is not yet exposed by this version of `solana_program`.
+const SET_AUTHORITY_CHECKED: u32 = 7;
+
+/// Returns the instruction that sets the upgrade authority of a program,
+/// requiring the signature of both the current and the new authority.
+pub fn set_upgrade_authority_checked(
+    program_address: &Pubkey,
+    current_authority_address: &Pubkey,
+    new_authority_address: &Pubkey,
+) -> Instruction {
+    let (programdata_address, _) = Pubkey::find_program_address(&[program_address.as_ref()], &ID);
+    Instruction::new_with_bincode(
+        ID,
+        &SET_AUTHORITY_CHECKED,
+        vec![
+            AccountMeta::new(programdata_address, false),
+            AccountMeta::new_readonly(*current_authority_address, true),
+            AccountMeta::new_readonly(*new_authority_address, true),
+        ],
+    )
+}
+
 impl Id for BPFLoaderUpgradeable {
     fn id() -> Pubkey {
         bpf_loader_upgradeable::ID
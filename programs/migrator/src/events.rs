@@ -0,0 +1,252 @@
+//! Events emitted by the migrator program.
+
+use crate::state::{MigrationKind, MigratorMetadata};
+use anchor_lang::prelude::*;
+
+/// Emitted when a [crate::state::Migrator] is created.
+#[event]
+pub struct NewMigratorEvent {
+    /// The [crate::state::Migrator].
+    #[index]
+    pub migrator: Pubkey,
+    /// [crate::state::Migrator::program_id].
+    pub program_id: Pubkey,
+    /// [crate::state::Migrator::approvers].
+    pub approvers: Vec<Pubkey>,
+    /// [crate::state::Migrator::threshold].
+    pub threshold: u8,
+    /// [crate::state::Migrator::min_delay].
+    pub min_delay: i64,
+    /// [crate::state::Migrator::name].
+    pub name: String,
+    /// When the [crate::state::Migrator] was created.
+    pub timestamp: i64,
+}
+
+/// Emitted when a program ID is reserved.
+#[event]
+pub struct ReserveProgramIDEvent {
+    /// The reserved program ID.
+    #[index]
+    pub program: Pubkey,
+    /// [crate::state::Reservation::reserver].
+    pub reserver: Pubkey,
+    /// [crate::state::Reservation::approver].
+    pub approver: Pubkey,
+    /// [crate::state::Reservation::payer].
+    pub payer: Pubkey,
+    /// When the program ID was reserved.
+    pub timestamp: i64,
+}
+
+/// Emitted when a [crate::state::Migration] is proposed.
+#[event]
+pub struct ProposeMigrationEvent {
+    /// The [crate::state::Migrator].
+    #[index]
+    pub migrator: Pubkey,
+    /// The proposed [crate::state::Migration].
+    pub migration: Pubkey,
+    /// [crate::state::Migration::index].
+    pub index: u64,
+    /// [crate::state::Migration::kind].
+    pub kind: MigrationKind,
+    /// [crate::state::Migration::buffer].
+    pub buffer: Pubkey,
+    /// [crate::state::Migration::proposer].
+    pub proposer: Pubkey,
+    /// [crate::state::Migration::bond].
+    pub bond: u64,
+    /// When the [crate::state::Migration] was proposed.
+    pub timestamp: i64,
+}
+
+/// Emitted when an approver approves a [crate::state::Migration].
+#[event]
+pub struct ApproveMigrationEvent {
+    /// The [crate::state::Migrator].
+    #[index]
+    pub migrator: Pubkey,
+    /// The approved [crate::state::Migration].
+    pub migration: Pubkey,
+    /// [crate::state::Migration::index].
+    pub index: u64,
+    /// The approver.
+    pub approver: Pubkey,
+    /// When the approval expires.
+    pub deadline: i64,
+    /// The approved sha256 of the program in the buffer.
+    pub buffer_hash: [u8; 32],
+    /// The approved length of the program in the buffer.
+    pub buffer_len: u64,
+    /// [crate::state::Migration::num_approvals], including this approval.
+    pub num_approvals: u8,
+    /// [crate::state::Migration::threshold].
+    pub threshold: u8,
+    /// True if this approval reached quorum, making the [crate::state::Migration]
+    /// the [crate::state::Migrator::pending_migration].
+    pub pending: bool,
+    /// [crate::state::Migrator::executable_after] if the [crate::state::Migration]
+    /// became pending, otherwise -1.
+    pub executable_after: i64,
+    /// When the [crate::state::Migration] was approved.
+    pub timestamp: i64,
+}
+
+/// Emitted when the approvers reject a [crate::state::Migration].
+#[event]
+pub struct RejectMigrationEvent {
+    /// The [crate::state::Migrator].
+    #[index]
+    pub migrator: Pubkey,
+    /// The rejected [crate::state::Migration].
+    pub migration: Pubkey,
+    /// [crate::state::Migration::index].
+    pub index: u64,
+    /// The approver which submitted the rejection.
+    pub approver: Pubkey,
+    /// True if the proposer's bond was forfeited as spam.
+    pub spam: bool,
+    /// When the [crate::state::Migration] was rejected.
+    pub timestamp: i64,
+}
+
+/// Emitted when the guardian vetoes a [crate::state::Migration].
+#[event]
+pub struct VetoMigrationEvent {
+    /// The [crate::state::Migrator].
+    #[index]
+    pub migrator: Pubkey,
+    /// The vetoed [crate::state::Migration].
+    pub migration: Pubkey,
+    /// [crate::state::Migration::index].
+    pub index: u64,
+    /// The guardian which vetoed the [crate::state::Migration].
+    pub guardian: Pubkey,
+    /// When the [crate::state::Migration] was vetoed.
+    pub timestamp: i64,
+}
+
+/// Emitted when a program is deployed by a [crate::state::Migration].
+#[event]
+pub struct DeployProgramEvent {
+    /// The [crate::state::Migrator].
+    #[index]
+    pub migrator: Pubkey,
+    /// The executed [crate::state::Migration].
+    pub migration: Pubkey,
+    /// [crate::state::Migration::index].
+    pub index: u64,
+    /// The deployed program.
+    pub program: Pubkey,
+    /// The buffer which was deployed.
+    pub buffer: Pubkey,
+    /// [crate::state::Migration::executor].
+    pub executor: Pubkey,
+    /// When the program was deployed.
+    pub timestamp: i64,
+}
+
+/// Emitted when a program is upgraded by a [crate::state::Migration].
+#[event]
+pub struct UpgradeProgramEvent {
+    /// The [crate::state::Migrator].
+    #[index]
+    pub migrator: Pubkey,
+    /// The executed [crate::state::Migration].
+    pub migration: Pubkey,
+    /// [crate::state::Migration::index].
+    pub index: u64,
+    /// The upgraded program.
+    pub program: Pubkey,
+    /// The buffer which the program was upgraded to.
+    pub buffer: Pubkey,
+    /// [crate::state::Migration::executor].
+    pub executor: Pubkey,
+    /// When the program was upgraded.
+    pub timestamp: i64,
+}
+
+/// Emitted when a program is made immutable by a [crate::state::Migration].
+#[event]
+pub struct FinalizeProgramEvent {
+    /// The [crate::state::Migrator].
+    #[index]
+    pub migrator: Pubkey,
+    /// The executed [crate::state::Migration].
+    pub migration: Pubkey,
+    /// [crate::state::Migration::index].
+    pub index: u64,
+    /// The finalized program.
+    pub program: Pubkey,
+    /// [crate::state::Migration::executor].
+    pub executor: Pubkey,
+    /// When the program was finalized.
+    pub timestamp: i64,
+}
+
+/// Emitted when the upgrade authority of a program is transferred by a [crate::state::Migration].
+#[event]
+pub struct TransferAuthorityEvent {
+    /// The [crate::state::Migrator].
+    #[index]
+    pub migrator: Pubkey,
+    /// The executed [crate::state::Migration].
+    pub migration: Pubkey,
+    /// [crate::state::Migration::index].
+    pub index: u64,
+    /// The program whose upgrade authority was transferred.
+    pub program: Pubkey,
+    /// The new upgrade authority of the program.
+    pub new_authority: Pubkey,
+    /// [crate::state::Migration::executor].
+    pub executor: Pubkey,
+    /// When the upgrade authority was transferred.
+    pub timestamp: i64,
+}
+
+/// Emitted when a proposer withdraws a [crate::state::Migration].
+#[event]
+pub struct WithdrawMigrationEvent {
+    /// The [crate::state::Migrator].
+    #[index]
+    pub migrator: Pubkey,
+    /// The withdrawn [crate::state::Migration].
+    pub migration: Pubkey,
+    /// [crate::state::Migration::index].
+    pub index: u64,
+    /// The buffer which was returned to the proposer.
+    pub buffer: Pubkey,
+    /// [crate::state::Migration::proposer].
+    pub proposer: Pubkey,
+    /// When the [crate::state::Migration] was withdrawn.
+    pub timestamp: i64,
+}
+
+/// Emitted when the [crate::state::MigratorMetadata] of a [crate::state::Migrator] is updated.
+#[event]
+pub struct UpdateMigratorMetadataEvent {
+    /// The [crate::state::Migrator].
+    #[index]
+    pub migrator: Pubkey,
+    /// The previous metadata.
+    pub old: MigratorMetadata,
+    /// The new metadata.
+    pub new: MigratorMetadata,
+    /// When the metadata was updated.
+    pub timestamp: i64,
+}
+
+/// Emitted when lamports are withdrawn from a [crate::state::Migrator].
+#[event]
+pub struct WithdrawLamportsEvent {
+    /// The [crate::state::Migrator].
+    #[index]
+    pub migrator: Pubkey,
+    /// The account which received the lamports.
+    pub recipient: Pubkey,
+    /// Number of lamports withdrawn.
+    pub amount: u64,
+    /// When the withdrawal took place.
+    pub timestamp: i64,
+}
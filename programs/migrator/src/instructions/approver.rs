@@ -1,12 +1,19 @@
 //! Instructions callable by the approver.
 
 use crate::account_contexts::*;
+use crate::bpf_loader_upgradeable::set_upgrade_authority_checked;
+use crate::events::*;
+use crate::lamports::transfer_lamports;
+use crate::realloc::realloc;
+use crate::state::{
+    validate_approver_set, MigrationKind, Migrator, MigratorMetadata, MIN_EJECT_DELAY,
+};
 use anchor_lang::prelude::*;
-use solana_program::bpf_loader_upgradeable;
+use solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
 use solana_program::{
     loader_upgradeable_instruction::UpgradeableLoaderInstruction, system_program, sysvar,
 };
-use vipers::unwrap_int;
+use vipers::{try_or_err, unwrap_int, unwrap_opt};
 
 /// Creates a new [Migrator].
 pub fn new_migrator(
@@ -14,24 +21,127 @@ pub fn new_migrator(
     bump: u8,
     name: String,
     description: String,
+    approvers: Vec<Pubkey>,
+    threshold: u8,
+    min_delay: i64,
 ) -> ProgramResult {
     let migrator = &mut ctx.accounts.migrator;
     migrator.program_id = ctx.accounts.program.key();
     migrator.bump = bump;
+    init_migrator(migrator, name, description, approvers, threshold, min_delay)?;
+
+    emit_new_migrator(migrator)
+}
+
+/// Creates a new [Migrator] for a live program, handing it the upgrade authority.
+pub fn adopt_program(
+    ctx: Context<AdoptProgram>,
+    bump: u8,
+    name: String,
+    description: String,
+    approvers: Vec<Pubkey>,
+    threshold: u8,
+    min_delay: i64,
+) -> ProgramResult {
+    let migrator = &mut ctx.accounts.migrator;
+    migrator.program_id = ctx.accounts.program.program.key();
+    migrator.bump = bump;
+    init_migrator(migrator, name, description, approvers, threshold, min_delay)?;
+
+    solana_program::program::invoke(
+        &bpf_loader_upgradeable::set_upgrade_authority(
+            &migrator.program_id,
+            &ctx.accounts.upgrade_authority.key(),
+            Some(&migrator.key()),
+        ),
+        &[
+            ctx.accounts.program.program_data.to_account_info(),
+            ctx.accounts.upgrade_authority.to_account_info(),
+            migrator.to_account_info(),
+        ],
+    )?;
+
+    emit_new_migrator(migrator)
+}
+
+/// Emits a [NewMigratorEvent] for a newly created [Migrator].
+fn emit_new_migrator(migrator: &Account<Migrator>) -> ProgramResult {
+    emit!(NewMigratorEvent {
+        migrator: migrator.key(),
+        program_id: migrator.program_id,
+        approvers: migrator.approvers.clone(),
+        threshold: migrator.threshold,
+        min_delay: migrator.min_delay,
+        name: migrator.name.clone(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Sets up the governance of a newly created [Migrator].
+fn init_migrator(
+    migrator: &mut Migrator,
+    name: String,
+    description: String,
+    approvers: Vec<Pubkey>,
+    threshold: u8,
+    min_delay: i64,
+) -> ProgramResult {
+    validate_approver_set(&approvers, threshold)?;
+    require!(min_delay >= 0, InvalidMinDelay);
 
-    migrator.approver = ctx.accounts.approver.key();
+    migrator.approvers = approvers;
+    migrator.threshold = threshold;
     migrator.pending_migration = Pubkey::default();
     migrator.approval_expires_at = -1;
+    migrator.executable_after = -1;
+    migrator.min_delay = min_delay;
+
+    migrator.proposed_approver = Pubkey::default();
+    migrator.replaced_approver = Pubkey::default();
+    migrator.rotation_proposed_at = -1;
+    migrator.num_rotations = 0;
+
+    migrator.guardian = Pubkey::default();
+    migrator.paused = false;
+
+    migrator.bond_amount = 0;
+    migrator.treasury = Pubkey::default();
+
+    migrator.eject_authority = Pubkey::default();
+    migrator.eject_after = -1;
 
     migrator.num_migrations = 0;
+    migrator.num_archived = 0;
+    migrator.num_open_migrations = 0;
     migrator.name = name;
     migrator.description = description;
+    migrator.website = String::new();
+    migrator.repository = String::new();
+    migrator.logo_uri = String::new();
 
     Ok(())
 }
 
 /// Deploys a program with a migration.
 pub fn deploy_program(ctx: Context<DeployProgram>) -> ProgramResult {
+    // for the first deploy, we will use a max data len of 2x the buffer.
+    let buffer_size: usize = ctx
+        .accounts
+        .approved_migration
+        .buffer
+        .to_account_info()
+        .data_len();
+    let max_data_len = unwrap_int!(buffer_size.checked_mul(2));
+
+    // pay for the program data out of the migration's funding
+    let programdata_len = try_or_err!(
+        UpgradeableLoaderState::programdata_len(max_data_len),
+        ParseError
+    );
+    let required = ctx.accounts.rent.minimum_balance(programdata_len);
+    ctx.accounts.approved_migration.spend_funding(required)?;
+
     let migrator = &ctx.accounts.approved_migration.migrator;
     let seeds = gen_migrator_signer_seeds!(migrator);
 
@@ -51,15 +161,6 @@ pub fn deploy_program(ctx: Context<DeployProgram>) -> ProgramResult {
     )?;
 
     // deploy the migration
-    // for the first deploy, we will use a max data len of 2x the buffer.
-    let buffer_size: usize = ctx
-        .accounts
-        .approved_migration
-        .buffer
-        .to_account_info()
-        .data_len();
-    let max_data_len = unwrap_int!(buffer_size.checked_mul(2));
-
     let deploy_ix = solana_program::instruction::Instruction::new_with_bincode(
         bpf_loader_upgradeable::ID,
         &UpgradeableLoaderInstruction::DeployWithMaxDataLen { max_data_len },
@@ -90,6 +191,17 @@ pub fn deploy_program(ctx: Context<DeployProgram>) -> ProgramResult {
     )?;
 
     ctx.accounts.approved_migration.commit()?;
+
+    let approved_migration = &ctx.accounts.approved_migration;
+    emit!(DeployProgramEvent {
+        migrator: approved_migration.migrator.key(),
+        migration: approved_migration.migration.key(),
+        index: approved_migration.migration.index,
+        program: ctx.accounts.program.program.key(),
+        buffer: approved_migration.buffer.key(),
+        executor: approved_migration.executor.key(),
+        timestamp: approved_migration.migration.executed_at,
+    });
     Ok(())
 }
 
@@ -120,39 +232,994 @@ pub fn upgrade_program(ctx: Context<UpgradeProgram>) -> ProgramResult {
     )?;
 
     ctx.accounts.approved_migration.commit()?;
+
+    let approved_migration = &ctx.accounts.approved_migration;
+    emit!(UpgradeProgramEvent {
+        migrator: approved_migration.migrator.key(),
+        migration: approved_migration.migration.key(),
+        index: approved_migration.migration.index,
+        program: ctx.accounts.program.program.key(),
+        buffer: approved_migration.buffer.key(),
+        executor: approved_migration.executor.key(),
+        timestamp: approved_migration.migration.executed_at,
+    });
     Ok(())
 }
 
-/// Approves a [Migration].
-pub fn approve_migration(ctx: Context<ApproveMigration>, deadline: i64) -> ProgramResult {
-    require!(
-        deadline > Clock::get()?.unix_timestamp,
-        ExpiryMustBeInFuture
+/// Makes a program immutable.
+pub fn finalize_program(ctx: Context<FinalizeProgram>) -> ProgramResult {
+    let migrator = &ctx.accounts.approved_action.migrator;
+    let seeds = gen_migrator_signer_seeds!(migrator);
+    solana_program::program::invoke_signed(
+        &bpf_loader_upgradeable::set_upgrade_authority(&migrator.program_id, &migrator.key(), None),
+        &[
+            ctx.accounts.program.program_data.to_account_info(),
+            migrator.to_account_info(),
+        ],
+        &[&seeds[..]],
+    )?;
+
+    ctx.accounts.approved_action.commit()?;
+
+    let approved_action = &ctx.accounts.approved_action;
+    emit!(FinalizeProgramEvent {
+        migrator: approved_action.migrator.key(),
+        migration: approved_action.migration.key(),
+        index: approved_action.migration.index,
+        program: ctx.accounts.program.program.key(),
+        executor: approved_action.executor.key(),
+        timestamp: approved_action.migration.executed_at,
+    });
+    Ok(())
+}
+
+/// Hands the upgrade authority of the program to the new authority
+/// of a [MigrationKind::TransferAuthority] migration.
+///
+/// If the new authority is another [Migrator] of the program, a quorum of its
+/// approvers must sign in the remaining accounts, and it signs the transfer itself.
+pub fn transfer_authority(ctx: Context<TransferAuthority>) -> ProgramResult {
+    let migrator = &ctx.accounts.approved_action.migrator;
+    let seeds = gen_migrator_signer_seeds!(migrator);
+    let ix = set_upgrade_authority_checked(
+        &migrator.program_id,
+        &migrator.key(),
+        &ctx.accounts.new_authority.key(),
     );
+    let accounts = [
+        ctx.accounts.program.program_data.to_account_info(),
+        migrator.to_account_info(),
+        ctx.accounts.new_authority.to_account_info(),
+    ];
+    match ctx.accounts.receiving_migrator()? {
+        Some(receiving_migrator) => {
+            receiving_migrator.assert_approver_quorum(
+                &ctx.accounts.approved_action.executor,
+                ctx.remaining_accounts,
+            )?;
+            let receiving_seeds = gen_migrator_signer_seeds!(receiving_migrator);
+            solana_program::program::invoke_signed(
+                &ix,
+                &accounts,
+                &[&seeds[..], &receiving_seeds[..]],
+            )?;
+        }
+        None => solana_program::program::invoke_signed(&ix, &accounts, &[&seeds[..]])?,
+    }
+
+    ctx.accounts.approved_action.commit()?;
+
+    let approved_action = &ctx.accounts.approved_action;
+    emit!(TransferAuthorityEvent {
+        migrator: approved_action.migrator.key(),
+        migration: approved_action.migration.key(),
+        index: approved_action.migration.index,
+        program: ctx.accounts.program.program.key(),
+        new_authority: ctx.accounts.new_authority.key(),
+        executor: approved_action.executor.key(),
+        timestamp: approved_action.migration.executed_at,
+    });
+    Ok(())
+}
+
+/// Approves a [Migration].
+///
+/// Once [Migration::threshold] approvals have been made, the [Migration]
+/// becomes the [Migrator::pending_migration], executable after [Migrator::min_delay].
+///
+/// Every approver must approve the same `buffer_hash` and `buffer_len`.
+pub fn approve_migration(
+    ctx: Context<ApproveMigration>,
+    bump: u8,
+    deadline: i64,
+    buffer_hash: [u8; 32],
+    buffer_len: u64,
+) -> ProgramResult {
+    let now = Clock::get()?.unix_timestamp;
+    require!(deadline > now, ExpiryMustBeInFuture);
+
+    let approval = &mut ctx.accounts.approval;
+    approval.migration = ctx.accounts.migration.key();
+    approval.approver = ctx.accounts.approver.key();
+    approval.bump = bump;
+    approval.approved_at = now;
+    approval.deadline = deadline;
+    approval.buffer_hash = buffer_hash;
+    approval.buffer_len = buffer_len;
 
-    // un-reject if the migration was rejected.
     let migration = &mut ctx.accounts.migration;
-    if migration.rejected_at != -1 {
-        migration.rejected_at = -1;
+    if let MigrationKind::EmbargoedUpgrade {
+        buffer_hash: committed_hash,
+        buffer_len: committed_len,
+    } = migration.kind
+    {
+        require!(
+            buffer_hash == committed_hash && buffer_len == committed_len,
+            BufferHashMismatch
+        );
+    }
+    if migration.num_approvals == 0 {
+        migration.approved_buffer_hash = buffer_hash;
+        migration.approved_buffer_len = buffer_len;
+    } else {
+        require!(
+            migration.approved_buffer_hash == buffer_hash
+                && migration.approved_buffer_len == buffer_len,
+            BufferHashMismatch
+        );
+    }
+    if migration.buffer_hashed_at != -1 {
+        require!(migration.buffer_matches_approval(), BufferHashMismatch);
     }
 
-    let migrator = &mut ctx.accounts.migrator;
-    migrator.pending_migration = ctx.accounts.migration.key();
-    migrator.approval_expires_at = deadline;
+    migration.num_approvals = unwrap_int!(migration.num_approvals.checked_add(1));
+    if migration.approval_deadline == -1 || deadline < migration.approval_deadline {
+        migration.approval_deadline = deadline;
+    }
+
+    let pending = migration.has_quorum();
+    let mut executable_after = -1;
+    if pending {
+        // un-reject if the migration was rejected.
+        if migration.rejected_at != -1 {
+            migration.rejected_at = -1;
+        }
+
+        let migrator = &mut ctx.accounts.migrator;
+        executable_after = unwrap_int!(now.checked_add(migrator.min_delay));
+        require!(
+            migration.approval_deadline > executable_after,
+            ApprovalWindowTooShort
+        );
+        migrator.pending_migration = migration.key();
+        migrator.approval_expires_at = migration.approval_deadline;
+        migrator.executable_after = executable_after;
+    }
+
+    emit!(ApproveMigrationEvent {
+        migrator: migration.migrator,
+        migration: migration.key(),
+        index: migration.index,
+        approver: ctx.accounts.approver.key(),
+        deadline,
+        buffer_hash,
+        buffer_len,
+        num_approvals: migration.num_approvals,
+        threshold: migration.threshold,
+        pending,
+        executable_after,
+        timestamp: now,
+    });
     Ok(())
 }
 
 /// Rejects the current [Migration].
-pub fn reject_migration(ctx: Context<RejectMigration>) -> ProgramResult {
+pub fn reject_migration(ctx: Context<RejectMigration>, spam: bool) -> ProgramResult {
     let migration = &mut ctx.accounts.migration;
     migration.rejected_at = Clock::get()?.unix_timestamp;
 
+    emit!(RejectMigrationEvent {
+        migrator: migration.migrator,
+        migration: migration.key(),
+        index: migration.index,
+        approver: ctx.accounts.approver.key(),
+        spam,
+        timestamp: migration.rejected_at,
+    });
+
+    // forfeit the bond to the treasury
+    if spam && migration.bond > 0 {
+        let treasury_key = ctx.accounts.migrator.treasury;
+        require!(treasury_key != Pubkey::default(), TreasuryMissing);
+        let treasury = ctx
+            .remaining_accounts
+            .iter()
+            .find(|info| *info.key == treasury_key)
+            .ok_or(crate::ErrorCode::TreasuryMissing)?;
+        transfer_lamports(&migration.to_account_info(), treasury, migration.bond)?;
+        migration.bond = 0;
+    }
+
     // cancel migration if it's the pending one
     let migrator = &mut ctx.accounts.migrator;
     if migrator.pending_migration.key() == migration.key() {
-        migrator.pending_migration = Pubkey::default();
-        migrator.approval_expires_at = -1;
+        migrator.clear_pending_migration();
     }
 
     Ok(())
 }
+
+/// Sets the [Migrator::guardian].
+pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Pubkey) -> ProgramResult {
+    ctx.accounts.migrator.guardian = guardian;
+    Ok(())
+}
+
+/// Updates the [MigratorMetadata], resizing the [Migrator] account to fit.
+pub fn update_migrator_metadata(
+    ctx: Context<UpdateMigratorMetadata>,
+    metadata: MigratorMetadata,
+) -> ProgramResult {
+    let migrator_info = ctx.accounts.migrator.to_account_info();
+    let old_len = migrator_info.data_len();
+    let new_len = Migrator::space(ctx.accounts.migrator.approvers.len(), &metadata);
+
+    let rent = Rent::get()?;
+    let old_rent = rent.minimum_balance(old_len);
+    let new_rent = rent.minimum_balance(new_len);
+    if new_rent > old_rent {
+        solana_program::program::invoke(
+            &solana_program::system_instruction::transfer(
+                ctx.accounts.payer.key,
+                migrator_info.key,
+                unwrap_int!(new_rent.checked_sub(old_rent)),
+            ),
+            &[
+                ctx.accounts.payer.to_account_info(),
+                migrator_info.clone(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+    } else {
+        transfer_lamports(
+            &migrator_info,
+            &ctx.accounts.payer,
+            unwrap_int!(old_rent.checked_sub(new_rent)),
+        )?;
+    }
+    realloc(&migrator_info, new_len)?;
+
+    let migrator = &mut ctx.accounts.migrator;
+    let old = migrator.metadata();
+    migrator.set_metadata(metadata.clone());
+
+    emit!(UpdateMigratorMetadataEvent {
+        migrator: migrator.key(),
+        old,
+        new: metadata,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Sets the [Migrator::bond_amount] and [Migrator::treasury].
+pub fn set_bond_config(
+    ctx: Context<SetBondConfig>,
+    bond_amount: u64,
+    treasury: Pubkey,
+) -> ProgramResult {
+    require!(
+        bond_amount == 0 || treasury != Pubkey::default(),
+        TreasuryRequired
+    );
+
+    let migrator = &mut ctx.accounts.migrator;
+    migrator.bond_amount = bond_amount;
+    migrator.treasury = treasury;
+    Ok(())
+}
+
+/// Withdraws lamports from the [Migrator], leaving enough for rent exemption.
+pub fn withdraw_lamports(ctx: Context<WithdrawLamports>, amount: u64) -> ProgramResult {
+    let migrator_info: AccountInfo = ctx.accounts.migrator.to_account_info();
+    let remaining = unwrap_int!(migrator_info.lamports().checked_sub(amount));
+    require!(
+        Rent::get()?.is_exempt(remaining, migrator_info.data_len()),
+        InsufficientLamports
+    );
+    transfer_lamports(&migrator_info, &ctx.accounts.recipient, amount)?;
+
+    emit!(WithdrawLamportsEvent {
+        migrator: migrator_info.key(),
+        recipient: ctx.accounts.recipient.key(),
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Proposes ejecting the [Migrator], handing the program's upgrade authority
+/// to `new_authority` after [Migrator::min_delay], or [MIN_EJECT_DELAY] if longer.
+pub fn propose_eject(ctx: Context<ProposeEject>, new_authority: Pubkey) -> ProgramResult {
+    require!(new_authority != Pubkey::default(), InvalidEjectAuthority);
+
+    let migrator = &mut ctx.accounts.migrator;
+    let now = Clock::get()?.unix_timestamp;
+    migrator.eject_authority = new_authority;
+    migrator.eject_after = unwrap_int!(now.checked_add(migrator.min_delay.max(MIN_EJECT_DELAY)));
+    Ok(())
+}
+
+/// Cancels a proposed ejection of the [Migrator].
+pub fn cancel_eject(ctx: Context<CancelEject>) -> ProgramResult {
+    let migrator = &mut ctx.accounts.migrator;
+    migrator.eject_authority = Pubkey::default();
+    migrator.eject_after = -1;
+    Ok(())
+}
+
+/// Ejects the [Migrator]: sets the upgrade authority of the program to
+/// [Migrator::eject_authority] and closes the [Migrator].
+pub fn eject_migrator(ctx: Context<EjectMigrator>) -> ProgramResult {
+    let migrator = &ctx.accounts.migrator;
+    let seeds = gen_migrator_signer_seeds!(migrator);
+    solana_program::program::invoke_signed(
+        &bpf_loader_upgradeable::set_upgrade_authority(
+            &migrator.program_id,
+            &migrator.key(),
+            Some(&migrator.eject_authority),
+        ),
+        &[
+            ctx.accounts.program.program_data.to_account_info(),
+            migrator.to_account_info(),
+            ctx.accounts.new_authority.to_account_info(),
+        ],
+        &[&seeds[..]],
+    )?;
+    Ok(())
+}
+
+/// Unpauses the [Migrator].
+pub fn unpause_migrator(ctx: Context<UnpauseMigrator>) -> ProgramResult {
+    ctx.accounts.migrator.paused = false;
+    Ok(())
+}
+
+/// Proposes replacing one of the [Migrator::approvers] with a new approver.
+pub fn propose_new_approver(
+    ctx: Context<ProposeNewApprover>,
+    old_approver: Pubkey,
+    new_approver: Pubkey,
+) -> ProgramResult {
+    let migrator = &mut ctx.accounts.migrator;
+    require!(migrator.is_approver(&old_approver), UnauthorizedApprover);
+    require!(
+        new_approver != Pubkey::default() && !migrator.is_approver(&new_approver),
+        InvalidApproverSet
+    );
+
+    migrator.proposed_approver = new_approver;
+    migrator.replaced_approver = old_approver;
+    migrator.rotation_proposed_at = Clock::get()?.unix_timestamp;
+    Ok(())
+}
+
+/// Accepts a rotation proposed via [crate::migrator::propose_new_approver].
+/// Any pending migration is cancelled.
+pub fn accept_approver(ctx: Context<AcceptApprover>, bump: u8) -> ProgramResult {
+    let migrator = &mut ctx.accounts.migrator;
+    let index = migrator.num_rotations;
+    migrator.num_rotations = unwrap_int!(migrator.num_rotations.checked_add(1));
+
+    let rotation = &mut ctx.accounts.rotation;
+    rotation.migrator = migrator.key();
+    rotation.index = index;
+    rotation.bump = bump;
+    rotation.old_approver = migrator.replaced_approver;
+    rotation.new_approver = migrator.proposed_approver;
+    rotation.proposed_at = migrator.rotation_proposed_at;
+    rotation.accepted_at = Clock::get()?.unix_timestamp;
+
+    let replaced = unwrap_opt!(
+        migrator
+            .approvers
+            .iter()
+            .position(|approver| *approver == rotation.old_approver),
+        "replaced approver not found"
+    );
+    migrator.approvers[replaced] = rotation.new_approver;
+
+    migrator.proposed_approver = Pubkey::default();
+    migrator.replaced_approver = Pubkey::default();
+    migrator.rotation_proposed_at = -1;
+    migrator.clear_pending_migration();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bpf_loader_upgradeable::UpgradeableLoaderAccount;
+    use crate::state::{
+        ApproverRotation, Migration, MigrationApproval, MigrationKind, Migrator, MigratorMetadata,
+        MIN_EJECT_DELAY,
+    };
+    use crate::test_utils::*;
+    use anchor_lang::prelude::*;
+    use solana_program::bpf_loader_upgradeable::UpgradeableLoaderState;
+
+    #[test]
+    fn approve_migration_reaches_quorum() {
+        let approver_key = Pubkey::new_unique();
+        let migrator_key = Pubkey::new_unique();
+        let migrator = Migrator {
+            approvers: vec![approver_key],
+            threshold: 1,
+            pending_migration: Pubkey::default(),
+            approval_expires_at: -1,
+            executable_after: -1,
+            ..Migrator::default()
+        };
+        let migration_key = Pubkey::new_unique();
+        let migration = new_migration(&migrator, migrator_key);
+        let (approval_key, bump) = Pubkey::find_program_address(
+            &[
+                b"approval".as_ref(),
+                migration_key.as_ref(),
+                approver_key.as_ref(),
+            ],
+            &crate::ID,
+        );
+
+        let accounts = [
+            program_account(migrator_key, &migrator),
+            program_account(migration_key, &migration),
+            uninitialized(approval_key),
+            wallet(approver_key),
+            wallet(Pubkey::new_unique()),
+            system_program_account(),
+        ];
+        let deadline = NOW + 1_000;
+        process(
+            &accounts,
+            crate::instruction::ApproveMigration {
+                bump,
+                deadline,
+                buffer_hash: [1; 32],
+                buffer_len: 100,
+            },
+        )
+        .unwrap();
+
+        let approval: MigrationApproval = load(&accounts[2]);
+        assert_eq!(approval.approver, approver_key);
+        assert_eq!(approval.deadline, deadline);
+        assert_eq!(approval.buffer_hash, [1; 32]);
+
+        let migration: Migration = load(&accounts[1]);
+        assert_eq!(migration.num_approvals, 1);
+        assert_eq!(migration.approved_buffer_len, 100);
+
+        let migrator: Migrator = load(&accounts[0]);
+        assert_eq!(migrator.pending_migration, migration_key);
+        assert_eq!(migrator.approval_expires_at, deadline);
+        assert_eq!(migrator.executable_after, NOW);
+    }
+
+    #[test]
+    fn accept_approver_replaces_approver() {
+        let old_approver = Pubkey::new_unique();
+        let new_approver = Pubkey::new_unique();
+        let migrator_key = Pubkey::new_unique();
+        let migrator = Migrator {
+            approvers: vec![old_approver],
+            threshold: 1,
+            proposed_approver: new_approver,
+            replaced_approver: old_approver,
+            rotation_proposed_at: NOW - 1,
+            num_rotations: 0,
+            ..Migrator::default()
+        };
+        let (rotation_key, bump) = Pubkey::find_program_address(
+            &[
+                b"rotation".as_ref(),
+                migrator_key.as_ref(),
+                0u64.to_le_bytes().as_ref(),
+            ],
+            &crate::ID,
+        );
+
+        let accounts = [
+            program_account(migrator_key, &migrator),
+            uninitialized(rotation_key),
+            wallet(new_approver),
+            wallet(Pubkey::new_unique()),
+            system_program_account(),
+        ];
+        process(&accounts, crate::instruction::AcceptApprover { bump }).unwrap();
+
+        let rotation: ApproverRotation = load(&accounts[1]);
+        assert_eq!(rotation.old_approver, old_approver);
+        assert_eq!(rotation.new_approver, new_approver);
+        assert_eq!(rotation.accepted_at, NOW);
+
+        let migrator: Migrator = load(&accounts[0]);
+        assert_eq!(migrator.approvers, vec![new_approver]);
+        assert_eq!(migrator.num_rotations, 1);
+        assert_eq!(migrator.proposed_approver, Pubkey::default());
+    }
+
+    #[test]
+    fn reject_migration_forfeits_bond_to_treasury() {
+        let approver_key = Pubkey::new_unique();
+        let treasury_key = Pubkey::new_unique();
+        let migrator_key = Pubkey::new_unique();
+        let migrator = Migrator {
+            approvers: vec![approver_key],
+            threshold: 1,
+            treasury: treasury_key,
+            ..Migrator::default()
+        };
+        let migration_key = Pubkey::new_unique();
+        let migration = Migration {
+            bond: 1_000,
+            ..new_migration(&migrator, migrator_key)
+        };
+
+        // without spam, no treasury is needed
+        let accounts = [
+            program_account(migrator_key, &migrator),
+            program_account(migration_key, &migration),
+            wallet(approver_key),
+        ];
+        process(
+            &accounts,
+            crate::instruction::RejectMigration { spam: false },
+        )
+        .unwrap();
+        let rejected: Migration = load(&accounts[1]);
+        assert_eq!(rejected.rejected_at, NOW);
+        assert_eq!(rejected.bond, 1_000);
+
+        // spam forfeits the bond to the treasury passed in the remaining accounts
+        let treasury = wallet(treasury_key);
+        let accounts = [
+            program_account(migrator_key, &migrator),
+            program_account(migration_key, &migration),
+            wallet(approver_key),
+            treasury.clone(),
+        ];
+        assert!(process(
+            &accounts[..3],
+            crate::instruction::RejectMigration { spam: true },
+        )
+        .is_err());
+        let treasury_lamports = treasury.lamports();
+        process(
+            &accounts,
+            crate::instruction::RejectMigration { spam: true },
+        )
+        .unwrap();
+        let rejected: Migration = load(&accounts[1]);
+        assert_eq!(rejected.bond, 0);
+        assert_eq!(treasury.lamports(), treasury_lamports + 1_000);
+    }
+
+    #[test]
+    fn approve_migration_rejects_reclaimed_buffer() {
+        let approver_key = Pubkey::new_unique();
+        let migrator_key = Pubkey::new_unique();
+        let migrator = Migrator {
+            approvers: vec![approver_key],
+            threshold: 1,
+            ..Migrator::default()
+        };
+        let migration_key = Pubkey::new_unique();
+        let migration = Migration {
+            rejected_at: NOW - 10,
+            buffer_reclaimed_at: NOW - 5,
+            ..new_migration(&migrator, migrator_key)
+        };
+        let (approval_key, bump) = Pubkey::find_program_address(
+            &[
+                b"approval".as_ref(),
+                migration_key.as_ref(),
+                approver_key.as_ref(),
+            ],
+            &crate::ID,
+        );
+
+        let accounts = [
+            program_account(migrator_key, &migrator),
+            program_account(migration_key, &migration),
+            uninitialized(approval_key),
+            wallet(approver_key),
+            wallet(Pubkey::new_unique()),
+            system_program_account(),
+        ];
+        let result = process(
+            &accounts,
+            crate::instruction::ApproveMigration {
+                bump,
+                deadline: NOW + 1_000,
+                buffer_hash: [1; 32],
+                buffer_len: 100,
+            },
+        );
+        assert_eq!(result, Err(crate::ErrorCode::BufferReclaimed.into()));
+    }
+
+    #[test]
+    fn upgrade_program_waits_for_timelock() {
+        let migrator_key = Pubkey::new_unique();
+        let migration_key = Pubkey::new_unique();
+        let program_key = Pubkey::new_unique();
+        let migrator = Migrator {
+            program_id: program_key,
+            threshold: 1,
+            pending_migration: migration_key,
+            approval_expires_at: NOW + 1_000,
+            executable_after: NOW + 10,
+            ..Migrator::default()
+        };
+        let migration = Migration {
+            num_approvals: 1,
+            ..new_migration(&migrator, migrator_key)
+        };
+
+        let [program, program_data] = live_program(program_key, migrator_key);
+        let [rent, clock] = rent_and_clock();
+        let accounts = [
+            program_account(migrator_key, &migrator),
+            program_account(migration_key, &migration),
+            buffer_account(migrator_key, &[]),
+            wallet(Pubkey::new_unique()),
+            wallet(migration.proposer),
+            program,
+            program_data,
+            rent,
+            clock,
+            system_program_account(),
+            bpf_loader_upgradeable_account(),
+        ];
+        assert_eq!(
+            process(&accounts, crate::instruction::UpgradeProgram {}),
+            Err(crate::ErrorCode::MigrationTimelocked.into())
+        );
+    }
+
+    #[test]
+    fn set_guardian_requires_quorum() {
+        let approvers = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let migrator_key = Pubkey::new_unique();
+        let migrator = Migrator {
+            approvers: approvers.to_vec(),
+            threshold: 2,
+            ..Migrator::default()
+        };
+        let guardian = Pubkey::new_unique();
+        let set_guardian = |signers: &[Pubkey]| {
+            let mut accounts = vec![program_account(migrator_key, &migrator)];
+            accounts.extend(signers.iter().map(|key| wallet(*key)));
+            let result = process(&accounts, crate::instruction::SetGuardian { guardian });
+            result.map(|()| load::<Migrator>(&accounts[0]).guardian)
+        };
+
+        assert_eq!(
+            set_guardian(&[Pubkey::new_unique(), approvers[0], approvers[1]]),
+            Err(crate::ErrorCode::UnauthorizedApprover.into())
+        );
+        assert_eq!(
+            set_guardian(&[approvers[0]]),
+            Err(crate::ErrorCode::InsufficientApprovers.into())
+        );
+        assert_eq!(set_guardian(&[approvers[0], approvers[1]]), Ok(guardian));
+    }
+
+    #[test]
+    fn eject_waits_for_delay_and_open_migrations() {
+        let approver_key = Pubkey::new_unique();
+        let migrator_key = Pubkey::new_unique();
+        let program_key = Pubkey::new_unique();
+        let new_authority = Pubkey::new_unique();
+        let migrator = Migrator {
+            program_id: program_key,
+            approvers: vec![approver_key],
+            threshold: 1,
+            min_delay: 0,
+            eject_after: -1,
+            num_open_migrations: 1,
+            ..Migrator::default()
+        };
+
+        let migrator_info = program_account(migrator_key, &migrator);
+        process(
+            &[migrator_info.clone(), wallet(approver_key)],
+            crate::instruction::ProposeEject { new_authority },
+        )
+        .unwrap();
+        let migrator: Migrator = load(&migrator_info);
+        assert_eq!(migrator.eject_after, NOW + MIN_EJECT_DELAY);
+
+        let [program, program_data] = live_program(program_key, migrator_key);
+        let accounts = [
+            program_account(
+                migrator_key,
+                &Migrator {
+                    eject_after: NOW,
+                    ..migrator
+                },
+            ),
+            wallet(approver_key),
+            program,
+            program_data,
+            nonexistent(new_authority),
+            wallet(Pubkey::new_unique()),
+            bpf_loader_upgradeable_account(),
+        ];
+        assert_eq!(
+            process(&accounts, crate::instruction::EjectMigrator {}),
+            Err(crate::ErrorCode::MigrationsOpen.into())
+        );
+    }
+
+    #[test]
+    fn finalize_program_requires_finalize_migration() {
+        let migrator_key = Pubkey::new_unique();
+        let migration_key = Pubkey::new_unique();
+        let program_key = Pubkey::new_unique();
+        let migrator = Migrator {
+            program_id: program_key,
+            threshold: 1,
+            pending_migration: migration_key,
+            approval_expires_at: NOW + 1_000,
+            executable_after: NOW,
+            ..Migrator::default()
+        };
+        // an approved upgrade cannot be used to finalize the program
+        let migration = Migration {
+            num_approvals: 1,
+            ..new_migration(&migrator, migrator_key)
+        };
+
+        let [program, program_data] = live_program(program_key, migrator_key);
+        let accounts = [
+            program_account(migrator_key, &migrator),
+            program_account(migration_key, &migration),
+            wallet(Pubkey::new_unique()),
+            wallet(migration.proposer),
+            program,
+            program_data,
+            bpf_loader_upgradeable_account(),
+        ];
+        assert_eq!(
+            process(&accounts, crate::instruction::FinalizeProgram {}),
+            Err(crate::ErrorCode::InvalidMigrationKind.into())
+        );
+    }
+
+    #[test]
+    fn transfer_authority_to_another_migrator() {
+        let program_key = Pubkey::new_unique();
+        let (migrator_key, bump) =
+            Pubkey::find_program_address(&[b"migrator".as_ref(), program_key.as_ref()], &crate::ID);
+        // a second migrator of the program, adopted with a non-canonical bump
+        let (receiving_key, receiving_bump) = (0..bump)
+            .rev()
+            .find_map(|bump| {
+                let seeds = [b"migrator".as_ref(), program_key.as_ref(), &[bump]];
+                let key = Pubkey::create_program_address(&seeds, &crate::ID).ok()?;
+                Some((key, bump))
+            })
+            .unwrap();
+        let migration_key = Pubkey::new_unique();
+        let migrator = Migrator {
+            program_id: program_key,
+            bump,
+            threshold: 1,
+            pending_migration: migration_key,
+            approval_expires_at: NOW + 1_000,
+            executable_after: NOW,
+            ..Migrator::default()
+        };
+        let migration = Migration {
+            kind: MigrationKind::TransferAuthority {
+                new_authority: receiving_key,
+            },
+            num_approvals: 1,
+            ..new_migration(&migrator, migrator_key)
+        };
+        let approvers = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let receiving_migrator = Migrator {
+            program_id: program_key,
+            bump: receiving_bump,
+            approvers: approvers.to_vec(),
+            threshold: 2,
+            ..Migrator::default()
+        };
+
+        let transfer_authority = |receiving_migrator: &Migrator, signers: &[Pubkey]| {
+            let [program, program_data] = live_program(program_key, migrator_key);
+            let mut accounts = vec![
+                program_account(migrator_key, &migrator),
+                program_account(migration_key, &migration),
+                wallet(Pubkey::new_unique()),
+                wallet(migration.proposer),
+                program,
+                program_data,
+                program_account(receiving_key, receiving_migrator),
+                bpf_loader_upgradeable_account(),
+            ];
+            accounts.extend(signers.iter().map(|key| wallet(*key)));
+            let result = process(&accounts, crate::instruction::TransferAuthority {});
+            result.map(|()| *load::<UpgradeableLoaderAccount>(&accounts[5]))
+        };
+
+        assert!(transfer_authority(
+            &Migrator {
+                program_id: Pubkey::new_unique(),
+                ..receiving_migrator.clone()
+            },
+            &approvers
+        )
+        .is_err());
+        assert_eq!(
+            transfer_authority(&receiving_migrator, &approvers[..1]),
+            Err(crate::ErrorCode::InsufficientApprovers.into())
+        );
+        assert_eq!(
+            transfer_authority(&receiving_migrator, &approvers),
+            Ok(UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: Some(receiving_key),
+            })
+        );
+    }
+
+    #[test]
+    fn adopt_program_requires_upgrade_authority() {
+        let program_key = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let (migrator_key, bump) =
+            Pubkey::find_program_address(&[b"migrator".as_ref(), program_key.as_ref()], &crate::ID);
+
+        let adopt_program = |signer: Pubkey| {
+            let [program, program_data] = live_program(program_key, authority);
+            let accounts = [
+                uninitialized(migrator_key),
+                program,
+                program_data,
+                wallet(signer),
+                wallet(Pubkey::new_unique()),
+                system_program_account(),
+                bpf_loader_upgradeable_account(),
+            ];
+            let result = process(
+                &accounts,
+                crate::instruction::AdoptProgram {
+                    bump,
+                    name: "Program".to_string(),
+                    description: String::new(),
+                    approvers: vec![authority],
+                    threshold: 1,
+                    min_delay: 0,
+                },
+            );
+            result.map(|()| *load::<UpgradeableLoaderAccount>(&accounts[2]))
+        };
+
+        assert_eq!(
+            adopt_program(Pubkey::new_unique()),
+            Err(vipers::VipersError::KeyMismatch.into())
+        );
+        assert_eq!(
+            adopt_program(authority),
+            Ok(UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: Some(migrator_key),
+            })
+        );
+    }
+
+    #[test]
+    fn update_migrator_metadata_moves_rent_to_and_from_payer() {
+        let approver_key = Pubkey::new_unique();
+        let migrator_key = Pubkey::new_unique();
+        let migrator = Migrator {
+            approvers: vec![approver_key],
+            threshold: 1,
+            ..Migrator::default()
+        };
+        let accounts = [
+            program_account(migrator_key, &migrator),
+            wallet(approver_key),
+            wallet(Pubkey::new_unique()),
+            system_program_account(),
+        ];
+        let rent = Rent::default();
+        let update = |metadata: MigratorMetadata| {
+            let old_rent = rent.minimum_balance(accounts[0].data_len());
+            let payer_lamports = accounts[2].lamports();
+            let migrator_lamports = accounts[0].lamports();
+            process(
+                &accounts,
+                crate::instruction::UpdateMigratorMetadata {
+                    metadata: metadata.clone(),
+                },
+            )
+            .unwrap();
+
+            let new_len = Migrator::space(1, &metadata);
+            assert_eq!(accounts[0].data_len(), new_len);
+            assert_eq!(load::<Migrator>(&accounts[0]).metadata(), metadata);
+            let rent_change = rent.minimum_balance(new_len) as i64 - old_rent as i64;
+            assert_eq!(
+                accounts[0].lamports() as i64 - migrator_lamports as i64,
+                rent_change
+            );
+            assert_eq!(
+                payer_lamports as i64 - accounts[2].lamports() as i64,
+                rent_change
+            );
+            rent_change
+        };
+
+        let grown = update(MigratorMetadata {
+            name: "Program".to_string(),
+            description: "A program with a long description.".repeat(10),
+            ..MigratorMetadata::default()
+        });
+        assert!(grown > 0);
+        let shrunk = update(MigratorMetadata {
+            name: "Program".to_string(),
+            ..MigratorMetadata::default()
+        });
+        assert!(shrunk < 0);
+    }
+
+    #[test]
+    fn upgrade_program_hashes_embargoed_buffer() {
+        let migrator_key = Pubkey::new_unique();
+        let migration_key = Pubkey::new_unique();
+        let program_key = Pubkey::new_unique();
+        let migrator = Migrator {
+            program_id: program_key,
+            threshold: 1,
+            pending_migration: migration_key,
+            approval_expires_at: NOW + 1_000,
+            executable_after: NOW,
+            ..Migrator::default()
+        };
+        let program = [1u8; 100];
+        let migration = Migration {
+            kind: MigrationKind::EmbargoedUpgrade {
+                buffer_hash: solana_program::hash::hash(&program).to_bytes(),
+                buffer_len: program.len() as u64,
+            },
+            buffer: Pubkey::default(),
+            num_approvals: 1,
+            approved_buffer_len: program.len() as u64,
+            ..new_migration(&migrator, migrator_key)
+        };
+
+        // the executor reveals a buffer of the approved length but different contents
+        let [program_info, program_data] = live_program(program_key, migrator_key);
+        let [rent, clock] = rent_and_clock();
+        let accounts = [
+            program_account(migrator_key, &migrator),
+            program_account(migration_key, &migration),
+            buffer_account(migrator_key, &[2u8; 100]),
+            wallet(Pubkey::new_unique()),
+            wallet(migration.proposer),
+            program_info,
+            program_data,
+            rent,
+            clock,
+            system_program_account(),
+            bpf_loader_upgradeable_account(),
+        ];
+        assert_eq!(
+            process(&accounts, crate::instruction::UpgradeProgram {}),
+            Err(crate::ErrorCode::BufferHashMismatch.into())
+        );
+    }
+}
@@ -0,0 +1,72 @@
+//! Instructions callable by the guardian.
+
+use crate::account_contexts::*;
+use crate::events::*;
+use anchor_lang::prelude::*;
+
+/// Pauses the [Migrator]. May also be called by any one of the [Migrator::approvers].
+pub fn pause_migrator(ctx: Context<PauseMigrator>) -> ProgramResult {
+    ctx.accounts.migrator.paused = true;
+    Ok(())
+}
+
+/// Vetoes the [Migrator::pending_migration].
+pub fn veto_migration(ctx: Context<VetoMigration>) -> ProgramResult {
+    let migration = &mut ctx.accounts.migration;
+    migration.vetoed_at = Clock::get()?.unix_timestamp;
+
+    ctx.accounts.migrator.clear_pending_migration();
+
+    emit!(VetoMigrationEvent {
+        migrator: migration.migrator,
+        migration: migration.key(),
+        index: migration.index,
+        guardian: ctx.accounts.guardian.key(),
+        timestamp: migration.vetoed_at,
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state::{Migration, Migrator};
+    use crate::test_utils::*;
+    use anchor_lang::prelude::*;
+
+    #[test]
+    fn veto_migration_ends_with_approval_window() {
+        let guardian_key = Pubkey::new_unique();
+        let migrator_key = Pubkey::new_unique();
+        let migration_key = Pubkey::new_unique();
+        let migrator = Migrator {
+            guardian: guardian_key,
+            threshold: 1,
+            pending_migration: migration_key,
+            approval_expires_at: NOW,
+            executable_after: NOW - 10,
+            ..Migrator::default()
+        };
+        let migration = Migration {
+            num_approvals: 1,
+            ..new_migration(&migrator, migrator_key)
+        };
+        let veto = |migrator: &Migrator| {
+            let accounts = [
+                program_account(migrator_key, migrator),
+                program_account(migration_key, &migration),
+                wallet(guardian_key),
+            ];
+            process(&accounts, crate::instruction::VetoMigration {})
+        };
+
+        assert_eq!(
+            veto(&migrator),
+            Err(crate::ErrorCode::MigrationWindowExpired.into())
+        );
+        veto(&Migrator {
+            approval_expires_at: NOW + 1,
+            ..migrator.clone()
+        })
+        .unwrap();
+    }
+}
@@ -1,2 +1,3 @@
 pub mod approver;
+pub mod guardian;
 pub mod public;
@@ -2,8 +2,15 @@
 
 use crate::account_contexts::*;
 use crate::bpf_loader_upgradeable::UpgradeableLoaderAccount;
+use crate::events::*;
+use crate::lamports::transfer_lamports;
+use crate::migrate::{close_buffer, return_buffer};
+use crate::realloc::realloc;
+use crate::sha256::{self, SHA256_IV};
+use crate::state::{BuildProvenance, MigrationKind, MigrationSummary};
 use anchor_lang::prelude::*;
-use vipers::unwrap_int;
+use solana_program::bpf_loader_upgradeable::UpgradeableLoaderState;
+use vipers::{try_or_err, unwrap_int, unwrap_opt};
 
 /// Proposes a [Migration].
 pub fn propose_migration(
@@ -11,34 +18,340 @@ pub fn propose_migration(
     bump: u8,
     title: String,
     description: String,
+    kind: MigrationKind,
+    provenance: BuildProvenance,
+    description_hash: [u8; 32],
 ) -> ProgramResult {
+    if description_hash != [0; 32] {
+        require!(description.is_empty(), SealedDescriptionNotEmpty);
+    }
+
+    let buffer = match kind {
+        MigrationKind::Upgrade => {
+            ctx.accounts.validate_buffer()?;
+            ctx.accounts.buffer.key()
+        }
+        MigrationKind::Finalize => Pubkey::default(),
+        MigrationKind::TransferAuthority { new_authority } => {
+            require!(
+                new_authority != Pubkey::default() && new_authority != ctx.accounts.migrator.key(),
+                InvalidNewAuthority
+            );
+            Pubkey::default()
+        }
+        // the buffer is supplied when the migration is executed
+        MigrationKind::EmbargoedUpgrade { .. } => Pubkey::default(),
+    };
+
     let migrator = &mut ctx.accounts.migrator;
     let index = migrator.num_migrations;
     migrator.num_migrations = unwrap_int!(migrator.num_migrations.checked_add(1));
+    migrator.num_open_migrations = unwrap_int!(migrator.num_open_migrations.checked_add(1));
 
     let migration = &mut ctx.accounts.migration;
     migration.migrator = migrator.key();
     migration.index = index;
     migration.bump = bump;
+    migration.kind = kind;
 
-    migration.buffer = ctx.accounts.buffer.key();
+    migration.buffer = buffer;
     migration.proposer = ctx.accounts.proposer.key();
+    migration.bond = migrator.bond_amount;
+    migration.bounty = 0;
+    migration.funding = 0;
+    migration.funding_at_execution = 0;
+    migration.funding_spent = 0;
+
+    migration.approvers = migrator.approvers.clone();
+    migration.threshold = migrator.threshold;
+    migration.num_approvals = 0;
+    migration.approval_deadline = -1;
 
     migration.created_at = Clock::get()?.unix_timestamp;
     migration.rejected_at = -1;
+    migration.vetoed_at = -1;
     migration.executed_at = -1;
     migration.executor = Pubkey::default();
+    migration.buffer_reclaimed_at = -1;
+
+    migration.provenance = provenance;
+
+    migration.buffer_hash_state = SHA256_IV;
+    migration.buffer_hash_offset = 0;
+    migration.buffer_hash = [0; 32];
+    migration.buffer_hashed_at = -1;
+    migration.approved_buffer_hash = [0; 32];
+    migration.approved_buffer_len = 0;
+
+    migration.description_hash = description_hash;
 
     migration.title = title;
     migration.description = description;
 
+    // escrow the proposer's bond in the migration
+    if migration.bond > 0 {
+        solana_program::program::invoke(
+            &solana_program::system_instruction::transfer(
+                ctx.accounts.proposer.key,
+                &migration.key(),
+                migration.bond,
+            ),
+            &[
+                ctx.accounts.proposer.to_account_info(),
+                migration.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+    }
+
+    emit!(ProposeMigrationEvent {
+        migrator: migration.migrator,
+        migration: migration.key(),
+        index: migration.index,
+        kind: migration.kind,
+        buffer: migration.buffer,
+        proposer: migration.proposer,
+        bond: migration.bond,
+        timestamp: migration.created_at,
+    });
+    Ok(())
+}
+
+/// Withdraws a [Migration], returning its buffer to the proposer and closing it.
+/// The bond and any bounty are refunded to the proposer along with the rent.
+pub fn withdraw_migration(ctx: Context<WithdrawMigration>) -> ProgramResult {
+    let migration = &ctx.accounts.migration;
+    if migration.has_buffer() && migration.buffer_reclaimed_at == -1 {
+        return_buffer(
+            &ctx.accounts.migrator,
+            ctx.accounts.buffer.to_account_info(),
+            ctx.accounts.proposer.to_account_info(),
+        )?;
+    }
+
+    let migrator = &mut ctx.accounts.migrator;
+    migrator.num_open_migrations = unwrap_int!(migrator.num_open_migrations.checked_sub(1));
+
+    let migration = &ctx.accounts.migration;
+    emit!(WithdrawMigrationEvent {
+        migrator: migration.migrator,
+        migration: migration.key(),
+        index: migration.index,
+        buffer: migration.buffer,
+        proposer: migration.proposer,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Returns the buffer of a rejected, vetoed, or expired [Migration] to its proposer,
+/// either by transferring its authority or by closing it.
+pub fn reclaim_buffer(ctx: Context<ReclaimBuffer>, close: bool) -> ProgramResult {
+    // the proposer may now rewrite the buffer, so the migration may never be executed
+    ctx.accounts.migration.buffer_reclaimed_at = Clock::get()?.unix_timestamp;
+
+    let buffer = ctx.accounts.buffer.to_account_info();
+    let proposer = ctx.accounts.proposer.to_account_info();
+    if close {
+        close_buffer(&ctx.accounts.migrator, buffer, proposer)
+    } else {
+        return_buffer(&ctx.accounts.migrator, buffer, proposer)
+    }
+}
+
+/// Creates the next [MigrationHistory] page of a [Migrator].
+pub fn new_history_page(ctx: Context<NewHistoryPage>, bump: u8) -> ProgramResult {
+    let history = &mut ctx.accounts.history;
+    history.migrator = ctx.accounts.migrator.key();
+    history.page = ctx.accounts.migrator.current_history_page();
+    history.bump = bump;
+    history.entries = vec![];
+    Ok(())
+}
+
+/// Archives a finished [Migration] into its [Migrator]'s [MigrationHistory], closing it.
+pub fn archive_migration(ctx: Context<ArchiveMigration>) -> ProgramResult {
+    let migration = &ctx.accounts.migration;
+    let summary = MigrationSummary {
+        index: migration.index,
+        kind: migration.kind,
+        buffer: migration.buffer,
+        proposer: migration.proposer,
+        executor: migration.executor,
+        created_at: migration.created_at,
+        rejected_at: migration.rejected_at,
+        vetoed_at: migration.vetoed_at,
+        executed_at: migration.executed_at,
+        outcome: unwrap_opt!(
+            migration.outcome(Clock::get()?.unix_timestamp),
+            "migration not finished"
+        ),
+    };
+    ctx.accounts.history.entries.push(summary);
+
+    let migrator = &mut ctx.accounts.migrator;
+    migrator.num_archived = unwrap_int!(migrator.num_archived.checked_add(1));
+    migrator.num_open_migrations = unwrap_int!(migrator.num_open_migrations.checked_sub(1));
+    Ok(())
+}
+
+/// Reveals the sealed [Migration::description], resizing the [Migration] to fit it.
+pub fn reveal_description(ctx: Context<RevealDescription>, description: String) -> ProgramResult {
+    let migration = &ctx.accounts.migration;
+    require!(
+        solana_program::hash::hash(description.as_bytes()).to_bytes() == migration.description_hash,
+        DescriptionHashMismatch
+    );
+
+    let migration_info = migration.to_account_info();
+    let new_len = unwrap_int!(migration_info.data_len().checked_add(description.len()));
+    let rent = Rent::get()?;
+    let extra_rent = rent
+        .minimum_balance(new_len)
+        .saturating_sub(rent.minimum_balance(migration_info.data_len()));
+    solana_program::program::invoke(
+        &solana_program::system_instruction::transfer(
+            ctx.accounts.payer.key,
+            migration_info.key,
+            extra_rent,
+        ),
+        &[
+            ctx.accounts.payer.to_account_info(),
+            migration_info.clone(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+    realloc(&migration_info, new_len)?;
+
+    ctx.accounts.migration.description = description;
+    Ok(())
+}
+
+/// Hashes up to `max_len` more bytes of the program in the [Migration::buffer].
+///
+/// The program is everything in the buffer after its [UpgradeableLoaderState::Buffer] header.
+/// Only whole sha256 blocks are hashed until fewer than one block remains,
+/// at which point the hash is finalized. `max_len` must be at least one block.
+pub fn hash_buffer(ctx: Context<HashBuffer>, max_len: u64) -> ProgramResult {
+    require!(max_len >= sha256::BLOCK_LEN as u64, HashLenTooSmall);
+
+    let buffer_info = ctx.accounts.buffer.to_account_info();
+    let data = buffer_info.try_borrow_data()?;
+    let data_offset = try_or_err!(UpgradeableLoaderState::buffer_data_offset(), ParseError);
+    let program = unwrap_opt!(data.get(data_offset..), "buffer header");
+
+    let migration = &mut ctx.accounts.migration;
+    let start = migration.buffer_hash_offset as usize;
+    let remaining = unwrap_opt!(program.get(start..), "buffer_hash_offset");
+    let len = (max_len as usize).min(remaining.len());
+
+    let consumed = sha256::update(&mut migration.buffer_hash_state, &remaining[..len]);
+    migration.buffer_hash_offset =
+        unwrap_int!(migration.buffer_hash_offset.checked_add(consumed as u64));
+
+    let tail = &remaining[consumed..];
+    if tail.len() < sha256::BLOCK_LEN {
+        migration.buffer_hash =
+            sha256::finalize(migration.buffer_hash_state, tail, program.len() as u64);
+        migration.buffer_hash_offset = program.len() as u64;
+        migration.buffer_hashed_at = Clock::get()?.unix_timestamp;
+    }
+
+    Ok(())
+}
+
+/// Adds to the bounty paid to the executor of a [Migration].
+pub fn fund_bounty(ctx: Context<FundBounty>, amount: u64) -> ProgramResult {
+    solana_program::program::invoke(
+        &solana_program::system_instruction::transfer(
+            ctx.accounts.proposer.key,
+            &ctx.accounts.migration.key(),
+            amount,
+        ),
+        &[
+            ctx.accounts.proposer.to_account_info(),
+            ctx.accounts.migration.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+
+    let migration = &mut ctx.accounts.migration;
+    migration.bounty = unwrap_int!(migration.bounty.checked_add(amount));
+    Ok(())
+}
+
+/// Creates a [MigrationDeposit] for a contributor.
+pub fn new_migration_deposit(ctx: Context<NewMigrationDeposit>, bump: u8) -> ProgramResult {
+    let deposit = &mut ctx.accounts.deposit;
+    deposit.migration = ctx.accounts.migration.key();
+    deposit.contributor = ctx.accounts.contributor.key();
+    deposit.bump = bump;
+    deposit.amount = 0;
+    Ok(())
+}
+
+/// Deposits lamports to pay for a [Migration].
+pub fn fund_migration(ctx: Context<FundMigration>, amount: u64) -> ProgramResult {
+    solana_program::program::invoke(
+        &solana_program::system_instruction::transfer(
+            ctx.accounts.contributor.key,
+            &ctx.accounts.migration.key(),
+            amount,
+        ),
+        &[
+            ctx.accounts.contributor.to_account_info(),
+            ctx.accounts.migration.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+
+    let deposit = &mut ctx.accounts.deposit;
+    deposit.amount = unwrap_int!(deposit.amount.checked_add(amount));
+
+    let migration = &mut ctx.accounts.migration;
+    migration.funding = unwrap_int!(migration.funding.checked_add(amount));
+    Ok(())
+}
+
+/// Refunds a [MigrationDeposit] and closes it.
+/// If the migration was executed, only the unspent share of the deposit is refunded.
+pub fn reclaim_deposit(ctx: Context<ReclaimDeposit>) -> ProgramResult {
+    let amount = ctx.accounts.deposit.amount;
+    let migration = &mut ctx.accounts.migration;
+    let refund = unwrap_int!(migration.refundable_amount(amount));
+    transfer_lamports(
+        &migration.to_account_info(),
+        &ctx.accounts.contributor.to_account_info(),
+        refund,
+    )?;
+    migration.funding = unwrap_int!(migration.funding.checked_sub(amount));
+    Ok(())
+}
+
+/// Releases a reserved program ID that was never deployed, refunding its rent to the payer.
+pub fn release_program_id(ctx: Context<ReleaseProgramID>) -> ProgramResult {
+    let program = &ctx.accounts.program;
+    transfer_lamports(program, &ctx.accounts.payer, program.lamports())?;
+    program.try_borrow_mut_data()?.fill(0);
     Ok(())
 }
 
 /// Reserves a new program ID to be administered by its migrator.
-pub fn reserve_program_id(ctx: Context<ReserveProgramID>) -> ProgramResult {
+pub fn reserve_program_id(
+    ctx: Context<ReserveProgramID>,
+    bump: u8,
+    approver: Pubkey,
+) -> ProgramResult {
     let program_address = ctx.accounts.program.key();
 
+    let reservation = &mut ctx.accounts.reservation;
+    reservation.program = program_address;
+    reservation.bump = bump;
+    reservation.reserver = ctx.accounts.reserver.key();
+    reservation.approver = approver;
+    reservation.payer = ctx.accounts.payer.key();
+    reservation.reserved_at = Clock::get()?.unix_timestamp;
+
     let rent = Rent::get()?;
     let min_program_balance =
         1.max(rent.minimum_balance(UpgradeableLoaderAccount::program_len().unwrap()));
@@ -55,8 +368,364 @@ pub fn reserve_program_id(ctx: Context<ReserveProgramID>) -> ProgramResult {
         &[
             ctx.accounts.system_program.to_account_info(),
             ctx.accounts.payer.to_account_info(),
+            ctx.accounts.program.to_account_info(),
         ],
     )?;
 
+    let reservation = &ctx.accounts.reservation;
+    emit!(ReserveProgramIDEvent {
+        program: program_address,
+        reserver: reservation.reserver,
+        approver: reservation.approver,
+        payer: reservation.payer,
+        timestamp: reservation.reserved_at,
+    });
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::state::{
+        BuildProvenance, Migration, MigrationDeposit, MigrationHistory, MigrationKind,
+        MigrationOutcome, Migrator, Reservation,
+    };
+    use crate::test_utils::*;
+    use anchor_lang::prelude::*;
+
+    #[test]
+    fn fund_migration_records_deposit() {
+        let migrator_key = Pubkey::new_unique();
+        let migrator = Migrator::default();
+        let migration_key = Pubkey::new_unique();
+        let migration = new_migration(&migrator, migrator_key);
+        let contributor_key = Pubkey::new_unique();
+        let (deposit_key, bump) = Pubkey::find_program_address(
+            &[
+                b"deposit".as_ref(),
+                migration_key.as_ref(),
+                contributor_key.as_ref(),
+            ],
+            &crate::ID,
+        );
+
+        let migration_info = program_account(migration_key, &migration);
+        let deposit_info = uninitialized(deposit_key);
+        let contributor = wallet(contributor_key);
+        process(
+            &[
+                migration_info.clone(),
+                deposit_info.clone(),
+                contributor.clone(),
+                contributor.clone(),
+                system_program_account(),
+            ],
+            crate::instruction::NewMigrationDeposit { bump },
+        )
+        .unwrap();
+
+        let migration_lamports = migration_info.lamports();
+        process(
+            &[
+                migration_info.clone(),
+                deposit_info.clone(),
+                contributor,
+                system_program_account(),
+            ],
+            crate::instruction::FundMigration { amount: 5_000 },
+        )
+        .unwrap();
+
+        let deposit: MigrationDeposit = load(&deposit_info);
+        assert_eq!(deposit.contributor, contributor_key);
+        assert_eq!(deposit.amount, 5_000);
+        let migration: Migration = load(&migration_info);
+        assert_eq!(migration.funding, 5_000);
+        assert_eq!(migration_info.lamports(), migration_lamports + 5_000);
+    }
+
+    #[test]
+    fn archive_migration_requires_reclaimed_buffer() {
+        let migrator_key = Pubkey::new_unique();
+        let migrator = Migrator {
+            num_open_migrations: 1,
+            ..Migrator::default()
+        };
+        let history_key = Pubkey::new_unique();
+        let history = MigrationHistory {
+            migrator: migrator_key,
+            ..MigrationHistory::default()
+        };
+        let migration = Migration {
+            rejected_at: NOW - 10,
+            ..new_migration(&migrator, migrator_key)
+        };
+        let archive = |migration: &Migration| {
+            let accounts = [
+                program_account(migrator_key, &migrator),
+                program_account(Pubkey::new_unique(), migration),
+                program_account_with_space(history_key, &history, 1_000),
+                account_info(
+                    migration.proposer,
+                    Pubkey::default(),
+                    0,
+                    vec![],
+                    false,
+                    true,
+                ),
+            ];
+            process(&accounts, crate::instruction::ArchiveMigration {})
+        };
+
+        assert_eq!(
+            archive(&migration),
+            Err(crate::ErrorCode::BufferNotReclaimed.into())
+        );
+        archive(&Migration {
+            buffer_reclaimed_at: NOW - 5,
+            ..migration.clone()
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn release_program_id_rejects_existing_migrator() {
+        let program_key = Pubkey::new_unique();
+        let reservation = Reservation {
+            program: program_key,
+            reserver: Pubkey::new_unique(),
+            payer: Pubkey::new_unique(),
+            ..Reservation::default()
+        };
+        let (migrator_key, _) = Pubkey::find_program_address(
+            &[b"migrator".as_ref(), &program_key.to_bytes()],
+            &crate::ID,
+        );
+        let release = |migrator: AccountInfo<'static>| {
+            let accounts = [
+                account_info(program_key, crate::ID, 1_000_000, vec![], false, true),
+                program_account(Pubkey::new_unique(), &reservation),
+                account_info(
+                    reservation.reserver,
+                    Pubkey::default(),
+                    0,
+                    vec![],
+                    true,
+                    false,
+                ),
+                account_info(reservation.payer, Pubkey::default(), 0, vec![], false, true),
+                migrator,
+            ];
+            process(&accounts, crate::instruction::ReleaseProgramId {})
+        };
+
+        assert_eq!(
+            release(program_account(migrator_key, &Migrator::default())),
+            Err(crate::ErrorCode::MigratorExists.into())
+        );
+        release(nonexistent(migrator_key)).unwrap();
+    }
+
+    #[test]
+    fn paused_migrator_rejects_proposals() {
+        let migrator_key = Pubkey::new_unique();
+        let migrator = Migrator {
+            paused: true,
+            ..Migrator::default()
+        };
+        let (migration_key, bump) = Pubkey::find_program_address(
+            &[b"migration".as_ref(), 0u64.to_le_bytes().as_ref()],
+            &crate::ID,
+        );
+        let propose = |migrator: &Migrator| {
+            let accounts = [
+                program_account(migrator_key, migrator),
+                uninitialized(migration_key),
+                nonexistent(Pubkey::default()),
+                wallet(Pubkey::new_unique()),
+                system_program_account(),
+            ];
+            process(
+                &accounts,
+                crate::instruction::ProposeMigration {
+                    bump,
+                    title: "Finalize".to_string(),
+                    description: String::new(),
+                    kind: MigrationKind::Finalize,
+                    provenance: BuildProvenance::default(),
+                    description_hash: [0; 32],
+                },
+            )
+        };
+
+        assert_eq!(
+            propose(&migrator),
+            Err(crate::ErrorCode::MigratorPaused.into())
+        );
+        propose(&Migrator {
+            paused: false,
+            ..migrator.clone()
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn withdraw_expired_migration_with_reclaimed_buffer() {
+        let migrator_key = Pubkey::new_unique();
+        let migration_key = Pubkey::new_unique();
+        let migrator = Migrator {
+            threshold: 1,
+            pending_migration: migration_key,
+            approval_expires_at: NOW - 1,
+            executable_after: NOW - 10,
+            num_open_migrations: 1,
+            ..Migrator::default()
+        };
+        let migration = Migration {
+            num_approvals: 1,
+            approval_deadline: NOW - 1,
+            buffer_reclaimed_at: NOW - 5,
+            ..new_migration(&migrator, migrator_key)
+        };
+
+        let migration_info = program_account(migration_key, &migration);
+        let proposer = wallet(migration.proposer);
+        let proposer_lamports = proposer.lamports();
+        process(
+            &[
+                program_account(migrator_key, &migrator),
+                migration_info.clone(),
+                account_info(migration.buffer, Pubkey::default(), 0, vec![], false, true),
+                proposer.clone(),
+                bpf_loader_upgradeable_account(),
+            ],
+            crate::instruction::WithdrawMigration {},
+        )
+        .unwrap();
+        assert_eq!(migration_info.lamports(), 0);
+        assert_eq!(proposer.lamports(), proposer_lamports + 1_000_000_000);
+    }
+
+    #[test]
+    fn anyone_may_reclaim_a_deposit_for_its_contributor() {
+        let migrator_key = Pubkey::new_unique();
+        let migrator = Migrator::default();
+        let migration_key = Pubkey::new_unique();
+        let migration = Migration {
+            funding: 5_000,
+            ..new_migration(&migrator, migrator_key)
+        };
+        let contributor_key = Pubkey::new_unique();
+        let deposit = MigrationDeposit {
+            migration: migration_key,
+            contributor: contributor_key,
+            bump: 0,
+            amount: 5_000,
+        };
+
+        let migration_info = program_account(migration_key, &migration);
+        let contributor = account_info(contributor_key, Pubkey::default(), 0, vec![], false, true);
+        process(
+            &[
+                program_account(migrator_key, &migrator),
+                migration_info.clone(),
+                program_account(Pubkey::new_unique(), &deposit),
+                contributor.clone(),
+            ],
+            crate::instruction::ReclaimDeposit {},
+        )
+        .unwrap();
+        assert_eq!(load::<Migration>(&migration_info).funding, 0);
+        // the refund and the rent of the deposit
+        assert_eq!(contributor.lamports(), 5_000 + 1_000_000_000);
+    }
+
+    #[test]
+    fn archive_expired_migration() {
+        let migrator_key = Pubkey::new_unique();
+        let migration_key = Pubkey::new_unique();
+        let migrator = Migrator {
+            threshold: 1,
+            pending_migration: migration_key,
+            approval_expires_at: NOW - 1,
+            executable_after: NOW - 10,
+            num_open_migrations: 1,
+            ..Migrator::default()
+        };
+        let history = MigrationHistory {
+            migrator: migrator_key,
+            ..MigrationHistory::default()
+        };
+        let migration = Migration {
+            num_approvals: 1,
+            approval_deadline: NOW - 1,
+            buffer_reclaimed_at: NOW - 5,
+            ..new_migration(&migrator, migrator_key)
+        };
+
+        let history_info = program_account_with_space(Pubkey::new_unique(), &history, 1_000);
+        process(
+            &[
+                program_account(migrator_key, &migrator),
+                program_account(migration_key, &migration),
+                history_info.clone(),
+                account_info(
+                    migration.proposer,
+                    Pubkey::default(),
+                    0,
+                    vec![],
+                    false,
+                    true,
+                ),
+            ],
+            crate::instruction::ArchiveMigration {},
+        )
+        .unwrap();
+        let history: MigrationHistory = load(&history_info);
+        assert_eq!(history.entries[0].outcome, MigrationOutcome::Expired);
+    }
+
+    #[test]
+    fn reveal_description_checks_hash_and_pays_rent() {
+        let migrator_key = Pubkey::new_unique();
+        let migration_key = Pubkey::new_unique();
+        let description = "Fixes a vulnerability in the vault.".to_string();
+        let migration = Migration {
+            executed_at: NOW,
+            description_hash: solana_program::hash::hash(description.as_bytes()).to_bytes(),
+            ..new_migration(&Migrator::default(), migrator_key)
+        };
+        let accounts = [
+            program_account(migration_key, &migration),
+            wallet(Pubkey::new_unique()),
+            system_program_account(),
+        ];
+        let old_len = accounts[0].data_len();
+        let migration_lamports = accounts[0].lamports();
+        let payer_lamports = accounts[1].lamports();
+
+        assert_eq!(
+            process(
+                &accounts,
+                crate::instruction::RevealDescription {
+                    description: "Something else.".to_string(),
+                },
+            ),
+            Err(crate::ErrorCode::DescriptionHashMismatch.into())
+        );
+        process(
+            &accounts,
+            crate::instruction::RevealDescription {
+                description: description.clone(),
+            },
+        )
+        .unwrap();
+
+        let new_len = old_len + description.len();
+        assert_eq!(accounts[0].data_len(), new_len);
+        assert_eq!(load::<Migration>(&accounts[0]).description, description);
+        let rent = Rent::default();
+        let extra_rent = rent.minimum_balance(new_len) - rent.minimum_balance(old_len);
+        assert_eq!(accounts[0].lamports(), migration_lamports + extra_rent);
+        assert_eq!(accounts[1].lamports(), payer_lamports - extra_rent);
+    }
+}
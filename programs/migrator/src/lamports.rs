@@ -0,0 +1,16 @@
+//! Helpers for moving lamports.
+
+use anchor_lang::prelude::*;
+use vipers::unwrap_int;
+
+/// Moves lamports out of an account owned by this program.
+pub fn transfer_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> ProgramResult {
+    if amount == 0 {
+        return Ok(());
+    }
+    let from_lamports = from.lamports();
+    **from.try_borrow_mut_lamports()? = unwrap_int!(from_lamports.checked_sub(amount));
+    let to_lamports = to.lamports();
+    **to.try_borrow_mut_lamports()? = unwrap_int!(to_lamports.checked_add(amount));
+    Ok(())
+}
@@ -6,12 +6,20 @@ mod macros;
 pub mod account_contexts;
 mod account_validators;
 pub mod bpf_loader_upgradeable;
+pub mod events;
 pub mod instructions;
+mod lamports;
 mod migrate;
+mod realloc;
+mod sha256;
 pub mod state;
+#[cfg(test)]
+mod test_utils;
 
 use account_contexts::*;
+use account_validators::ApproverQuorum;
 use anchor_lang::prelude::*;
+use state::{BuildProvenance, MigrationKind, MigratorMetadata};
 use vipers::validate::Validate;
 
 declare_id!("M1G1VdgdfvjMCdUhVtzaejnutPmLknEiraq2F59YGxr");
@@ -31,9 +39,43 @@ pub mod migrator {
         bump: u8,
         name: String,
         description: String,
+        approvers: Vec<Pubkey>,
+        threshold: u8,
+        min_delay: i64,
     ) -> ProgramResult {
         ctx.accounts.validate()?;
-        instructions::approver::new_migrator(ctx, bump, name, description)
+        instructions::approver::new_migrator(
+            ctx,
+            bump,
+            name,
+            description,
+            approvers,
+            threshold,
+            min_delay,
+        )
+    }
+
+    /// Creates a new [Migrator] for a live program, transferring the upgrade authority
+    /// from its current holder to the [Migrator] in the same instruction.
+    pub fn adopt_program(
+        ctx: Context<AdoptProgram>,
+        bump: u8,
+        name: String,
+        description: String,
+        approvers: Vec<Pubkey>,
+        threshold: u8,
+        min_delay: i64,
+    ) -> ProgramResult {
+        ctx.accounts.validate()?;
+        instructions::approver::adopt_program(
+            ctx,
+            bump,
+            name,
+            description,
+            approvers,
+            threshold,
+            min_delay,
+        )
     }
 
     /// Deploys a program with a migration.
@@ -48,15 +90,134 @@ pub mod migrator {
         instructions::approver::upgrade_program(ctx)
     }
 
-    /// Approves a [Migration].
-    pub fn approve_migration(ctx: Context<ApproveMigration>, deadline: i64) -> ProgramResult {
+    /// Makes a program immutable with a [MigrationKind::Finalize] migration.
+    pub fn finalize_program(ctx: Context<FinalizeProgram>) -> ProgramResult {
+        ctx.accounts.validate()?;
+        instructions::approver::finalize_program(ctx)
+    }
+
+    /// Hands the upgrade authority of a program to the new authority of a
+    /// [MigrationKind::TransferAuthority] migration.
+    /// A receiving [Migrator] of the same program is co-signed by a quorum of
+    /// its approvers, passed as signers in the remaining accounts.
+    pub fn transfer_authority(ctx: Context<TransferAuthority>) -> ProgramResult {
+        ctx.accounts.validate()?;
+        instructions::approver::transfer_authority(ctx)
+    }
+
+    /// Records an approval of a [Migration] by one of its approvers.
+    pub fn approve_migration(
+        ctx: Context<ApproveMigration>,
+        bump: u8,
+        deadline: i64,
+        buffer_hash: [u8; 32],
+        buffer_len: u64,
+    ) -> ProgramResult {
+        ctx.accounts.validate()?;
+        instructions::approver::approve_migration(ctx, bump, deadline, buffer_hash, buffer_len)
+    }
+
+    /// Rejects a [Migration]. Requires a quorum of approvers.
+    /// If `spam` is set, the proposer's bond is forfeited to the treasury.
+    pub fn reject_migration(ctx: Context<RejectMigration>, spam: bool) -> ProgramResult {
+        ctx.accounts.validate()?;
+        ctx.accounts.assert_quorum(ctx.remaining_accounts)?;
+        instructions::approver::reject_migration(ctx, spam)
+    }
+
+    /// Sets the [Migrator::guardian]. Requires a quorum of approvers.
+    pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Pubkey) -> ProgramResult {
+        ctx.accounts.assert_quorum(ctx.remaining_accounts)?;
+        instructions::approver::set_guardian(ctx, guardian)
+    }
+
+    /// Updates the [MigratorMetadata] of the [Migrator], resizing its account to fit.
+    /// Requires a quorum of approvers.
+    pub fn update_migrator_metadata(
+        ctx: Context<UpdateMigratorMetadata>,
+        metadata: MigratorMetadata,
+    ) -> ProgramResult {
+        ctx.accounts.assert_quorum(ctx.remaining_accounts)?;
+        instructions::approver::update_migrator_metadata(ctx, metadata)
+    }
+
+    /// Sets the spam bond required to propose a [Migration]. Requires a quorum of approvers.
+    pub fn set_bond_config(
+        ctx: Context<SetBondConfig>,
+        bond_amount: u64,
+        treasury: Pubkey,
+    ) -> ProgramResult {
+        ctx.accounts.assert_quorum(ctx.remaining_accounts)?;
+        instructions::approver::set_bond_config(ctx, bond_amount, treasury)
+    }
+
+    /// Withdraws lamports in excess of rent exemption from the [Migrator].
+    /// Requires a quorum of approvers.
+    pub fn withdraw_lamports(ctx: Context<WithdrawLamports>, amount: u64) -> ProgramResult {
+        ctx.accounts.assert_quorum(ctx.remaining_accounts)?;
+        instructions::approver::withdraw_lamports(ctx, amount)
+    }
+
+    /// Proposes handing the program's upgrade authority to `new_authority` and closing
+    /// the [Migrator] after [Migrator::min_delay], or [state::MIN_EJECT_DELAY] if longer.
+    /// Requires a quorum of approvers.
+    pub fn propose_eject(ctx: Context<ProposeEject>, new_authority: Pubkey) -> ProgramResult {
+        ctx.accounts.assert_quorum(ctx.remaining_accounts)?;
+        instructions::approver::propose_eject(ctx, new_authority)
+    }
+
+    /// Cancels a proposed ejection. Requires a quorum of approvers.
+    pub fn cancel_eject(ctx: Context<CancelEject>) -> ProgramResult {
+        ctx.accounts.validate()?;
+        ctx.accounts.assert_quorum(ctx.remaining_accounts)?;
+        instructions::approver::cancel_eject(ctx)
+    }
+
+    /// Ejects the [Migrator] once its timelock has elapsed, handing the program's
+    /// upgrade authority to the proposed authority. All of its migrations must first be
+    /// withdrawn or archived. Requires a quorum of approvers.
+    pub fn eject_migrator(ctx: Context<EjectMigrator>) -> ProgramResult {
+        ctx.accounts.validate()?;
+        ctx.accounts.assert_quorum(ctx.remaining_accounts)?;
+        instructions::approver::eject_migrator(ctx)
+    }
+
+    /// Unpauses the [Migrator]. Requires a quorum of approvers.
+    pub fn unpause_migrator(ctx: Context<UnpauseMigrator>) -> ProgramResult {
+        ctx.accounts.assert_quorum(ctx.remaining_accounts)?;
+        instructions::approver::unpause_migrator(ctx)
+    }
+
+    /// Proposes replacing one of the [Migrator::approvers]. Requires a quorum of approvers.
+    pub fn propose_new_approver(
+        ctx: Context<ProposeNewApprover>,
+        old_approver: Pubkey,
+        new_approver: Pubkey,
+    ) -> ProgramResult {
+        ctx.accounts.assert_quorum(ctx.remaining_accounts)?;
+        instructions::approver::propose_new_approver(ctx, old_approver, new_approver)
+    }
+
+    /// Accepts a proposed approver rotation. Must be signed by the new approver.
+    pub fn accept_approver(ctx: Context<AcceptApprover>, bump: u8) -> ProgramResult {
         ctx.accounts.validate()?;
-        instructions::approver::approve_migration(ctx, deadline)
+        instructions::approver::accept_approver(ctx, bump)
     }
-    /// Approves a [Migration].
-    pub fn reject_migration(ctx: Context<RejectMigration>) -> ProgramResult {
+
+    //////////////////////////////////////////
+    // Guardian instructions
+    //////////////////////////////////////////
+
+    /// Pauses the [Migrator]. Callable by the guardian or any approver.
+    pub fn pause_migrator(ctx: Context<PauseMigrator>) -> ProgramResult {
         ctx.accounts.validate()?;
-        instructions::approver::reject_migration(ctx)
+        instructions::guardian::pause_migrator(ctx)
+    }
+
+    /// Vetoes the pending [Migration] during its approval window.
+    pub fn veto_migration(ctx: Context<VetoMigration>) -> ProgramResult {
+        ctx.accounts.validate()?;
+        instructions::guardian::veto_migration(ctx)
     }
 
     //////////////////////////////////////////
@@ -69,15 +230,108 @@ pub mod migrator {
         bump: u8,
         title: String,
         description: String,
+        kind: MigrationKind,
+        provenance: BuildProvenance,
+        description_hash: [u8; 32],
+    ) -> ProgramResult {
+        ctx.accounts.validate()?;
+        instructions::public::propose_migration(
+            ctx,
+            bump,
+            title,
+            description,
+            kind,
+            provenance,
+            description_hash,
+        )
+    }
+
+    /// Reveals the sealed description of an executed [state::Migration].
+    pub fn reveal_description(
+        ctx: Context<RevealDescription>,
+        description: String,
     ) -> ProgramResult {
         ctx.accounts.validate()?;
-        instructions::public::propose_migration(ctx, bump, title, description)
+        instructions::public::reveal_description(ctx, description)
+    }
+
+    /// Withdraws an unapproved [Migration], returning its buffer to the proposer.
+    /// Must be signed by the proposer.
+    pub fn withdraw_migration(ctx: Context<WithdrawMigration>) -> ProgramResult {
+        ctx.accounts.validate()?;
+        instructions::public::withdraw_migration(ctx)
+    }
+
+    /// Returns the buffer of a rejected, vetoed, or expired [Migration] to its proposer.
+    /// If `close` is set, the buffer is closed and its lamports are sent to the proposer.
+    pub fn reclaim_buffer(ctx: Context<ReclaimBuffer>, close: bool) -> ProgramResult {
+        ctx.accounts.validate()?;
+        instructions::public::reclaim_buffer(ctx, close)
+    }
+
+    /// Creates the next [MigrationHistory] page of a [Migrator].
+    pub fn new_history_page(ctx: Context<NewHistoryPage>, bump: u8) -> ProgramResult {
+        ctx.accounts.validate()?;
+        instructions::public::new_history_page(ctx, bump)
+    }
+
+    /// Archives an executed, rejected, vetoed, or expired [Migration], closing it.
+    pub fn archive_migration(ctx: Context<ArchiveMigration>) -> ProgramResult {
+        ctx.accounts.validate()?;
+        instructions::public::archive_migration(ctx)
+    }
+
+    /// Hashes up to `max_len` more bytes of the [state::Migration::buffer],
+    /// storing the sha256 of the program once the whole buffer has been hashed.
+    /// `max_len` must be at least one sha256 block (64 bytes).
+    pub fn hash_buffer(ctx: Context<HashBuffer>, max_len: u64) -> ProgramResult {
+        ctx.accounts.validate()?;
+        instructions::public::hash_buffer(ctx, max_len)
+    }
+
+    /// Adds lamports to the bounty paid to whoever executes a [Migration].
+    /// Must be signed by the proposer.
+    pub fn fund_bounty(ctx: Context<FundBounty>, amount: u64) -> ProgramResult {
+        ctx.accounts.validate()?;
+        instructions::public::fund_bounty(ctx, amount)
+    }
+
+    /// Creates a [MigrationDeposit] to record a contributor's funding of a [Migration].
+    pub fn new_migration_deposit(ctx: Context<NewMigrationDeposit>, bump: u8) -> ProgramResult {
+        ctx.accounts.validate()?;
+        instructions::public::new_migration_deposit(ctx, bump)
+    }
+
+    /// Deposits lamports towards paying for a [Migration].
+    pub fn fund_migration(ctx: Context<FundMigration>, amount: u64) -> ProgramResult {
+        ctx.accounts.validate()?;
+        instructions::public::fund_migration(ctx, amount)
+    }
+
+    /// Reclaims a deposit, or its unspent share if the [Migration] was executed.
+    /// Anyone may reclaim a deposit; the refund always goes to its contributor.
+    pub fn reclaim_deposit(ctx: Context<ReclaimDeposit>) -> ProgramResult {
+        ctx.accounts.validate()?;
+        instructions::public::reclaim_deposit(ctx)
     }
 
     /// Reserves a new program ID to be administered by its migrator.
-    pub fn reserve_program_id(ctx: Context<ReserveProgramID>) -> ProgramResult {
+    ///
+    /// Only the reserver, or the `approver` it designates, may create the [Migrator].
+    pub fn reserve_program_id(
+        ctx: Context<ReserveProgramID>,
+        bump: u8,
+        approver: Pubkey,
+    ) -> ProgramResult {
+        ctx.accounts.validate()?;
+        instructions::public::reserve_program_id(ctx, bump, approver)
+    }
+
+    /// Releases a reserved program ID that was never deployed, refunding its rent.
+    /// Fails once a [Migrator] has been created for the program ID.
+    pub fn release_program_id(ctx: Context<ReleaseProgramID>) -> ProgramResult {
         ctx.accounts.validate()?;
-        instructions::public::reserve_program_id(ctx)
+        instructions::public::release_program_id(ctx)
     }
 }
 
@@ -101,4 +355,82 @@ pub enum ErrorCode {
     MigrationAlreadyExecuted,
     #[msg("Migration expiry time must be in the future.")]
     ExpiryMustBeInFuture,
+    #[msg("Invalid approver set.")]
+    InvalidApproverSet,
+    #[msg("Threshold must be between 1 and the number of approvers.")]
+    InvalidThreshold,
+    #[msg("Signer is not an approver.")]
+    UnauthorizedApprover,
+    #[msg("Not enough approvers signed.")]
+    InsufficientApprovers,
+    #[msg("Migration has not been approved by a quorum of approvers.")]
+    QuorumNotReached,
+    #[msg("Minimum delay must not be negative.")]
+    InvalidMinDelay,
+    #[msg("Approval deadline must be after the timelock expires.")]
+    ApprovalWindowTooShort,
+    #[msg("Migration timelock has not yet expired.")]
+    MigrationTimelocked,
+    #[msg("No approver rotation is pending.")]
+    NoPendingRotation,
+    #[msg("Signer is not the guardian.")]
+    UnauthorizedGuardian,
+    #[msg("Migration was vetoed by the guardian.")]
+    MigrationVetoed,
+    #[msg("Migrator is paused.")]
+    MigratorPaused,
+    #[msg("Signer may not pause the migrator.")]
+    UnauthorizedPauser,
+    #[msg("A treasury must be set to require a bond.")]
+    TreasuryRequired,
+    #[msg("The treasury must be passed in the remaining accounts to forfeit a bond.")]
+    TreasuryMissing,
+    #[msg("Deposits cannot be reclaimed while the migration is approved.")]
+    DepositLocked,
+    #[msg("Migration is pending execution.")]
+    MigrationPending,
+    #[msg("Migration has deposits which have not been reclaimed.")]
+    MigrationHasDeposits,
+    #[msg("Migration has not been rejected, vetoed, or expired.")]
+    MigrationNotFinished,
+    #[msg("History page is not the current page of the migrator.")]
+    HistoryPageMismatch,
+    #[msg("Eject authority must be set.")]
+    InvalidEjectAuthority,
+    #[msg("No ejection has been proposed.")]
+    NoPendingEject,
+    #[msg("Eject timelock has not yet expired.")]
+    EjectTimelocked,
+    #[msg("Instruction does not match the migration kind.")]
+    InvalidMigrationKind,
+    #[msg("New upgrade authority must be set and differ from the migrator.")]
+    InvalidNewAuthority,
+    #[msg("Only the reserver or its designated approver may create the migrator.")]
+    UnauthorizedReserver,
+    #[msg("Buffer has already been hashed.")]
+    BufferAlreadyHashed,
+    #[msg("Buffer has not been hashed.")]
+    BufferNotHashed,
+    #[msg("Buffer does not match the approved hash and length.")]
+    BufferHashMismatch,
+    #[msg("A sealed migration may not have a public description.")]
+    SealedDescriptionNotEmpty,
+    #[msg("Migration description is not sealed.")]
+    DescriptionNotSealed,
+    #[msg("Migration has not been executed.")]
+    MigrationNotExecuted,
+    #[msg("Description does not match the sealed hash.")]
+    DescriptionHashMismatch,
+    #[msg("The buffer of this migration was returned to the proposer.")]
+    BufferReclaimed,
+    #[msg("The buffer must be reclaimed before the migration is archived.")]
+    BufferNotReclaimed,
+    #[msg("The program ID already has a migrator.")]
+    MigratorExists,
+    #[msg("Must hash at least one sha256 block at a time.")]
+    HashLenTooSmall,
+    #[msg("All migrations must be withdrawn or archived first.")]
+    MigrationsOpen,
+    #[msg("New upgrade authority must sign or be a migrator of the same program.")]
+    NewAuthorityNotSigner,
 }
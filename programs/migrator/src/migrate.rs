@@ -1,27 +1,116 @@
-use crate::ApprovedMigration;
+use crate::lamports::transfer_lamports;
+use crate::state::{Migration, MigrationKind, Migrator};
+use crate::{ApprovedAction, ApprovedMigration};
 use anchor_lang::prelude::*;
+use solana_program::bpf_loader_upgradeable;
+
+/// Transfers the authority of a buffer held by the [Migrator] to `new_authority`.
+pub fn return_buffer<'info>(
+    migrator: &Account<'info, Migrator>,
+    buffer: AccountInfo<'info>,
+    new_authority: AccountInfo<'info>,
+) -> ProgramResult {
+    let seeds = gen_migrator_signer_seeds!(migrator);
+    solana_program::program::invoke_signed(
+        &bpf_loader_upgradeable::set_buffer_authority(
+            buffer.key,
+            &migrator.key(),
+            new_authority.key,
+        ),
+        &[buffer, migrator.to_account_info(), new_authority],
+        &[&seeds[..]],
+    )
+}
+
+/// Closes a buffer held by the [Migrator], sending its lamports to `recipient`.
+pub fn close_buffer<'info>(
+    migrator: &Account<'info, Migrator>,
+    buffer: AccountInfo<'info>,
+    recipient: AccountInfo<'info>,
+) -> ProgramResult {
+    let seeds = gen_migrator_signer_seeds!(migrator);
+    solana_program::program::invoke_signed(
+        &bpf_loader_upgradeable::close(buffer.key, recipient.key, &migrator.key()),
+        &[buffer, recipient, migrator.to_account_info()],
+        &[&seeds[..]],
+    )
+}
 
 impl<'info> ApprovedMigration<'info> {
+    /// Moves up to `required` lamports of the [crate::state::Migration::funding]
+    /// to the [crate::state::Migrator], which pays for the migration.
+    pub fn spend_funding(&mut self, required: u64) -> ProgramResult {
+        let migration = &mut self.migration;
+        let amount = required.min(migration.funding);
+        transfer_lamports(
+            &migration.to_account_info(),
+            &self.migrator.to_account_info(),
+            amount,
+        )?;
+        migration.funding_spent = amount;
+        Ok(())
+    }
+
     /// Commit the result of a successful migration.
     pub fn commit(&mut self) -> ProgramResult {
-        let migration = &mut self.migration;
-        migration.executed_at = Clock::get()?.unix_timestamp;
-        migration.executor = self.executor.key();
-
-        let migrator = &mut self.migrator;
-        migrator.pending_migration = Pubkey::default();
-        migrator.approval_expires_at = -1;
-        migrator.latest_migration_index = migration.index;
-
-        // ensure we still have enough lamports for rent exemption
-        let rent = Rent::get()?;
-        self.migrator.reload()?;
-        let migrator_info: AccountInfo = self.migrator.to_account_info();
-        require!(
-            rent.is_exempt(migrator_info.lamports(), migrator_info.data_len()),
-            InsufficientLamports
-        );
+        if let MigrationKind::EmbargoedUpgrade { buffer_hash, .. } = self.migration.kind {
+            // record the revealed buffer
+            let migration = &mut self.migration;
+            migration.buffer = self.buffer.key();
+            migration.buffer_hash = buffer_hash;
+            migration.buffer_hash_offset = migration.approved_buffer_len;
+            migration.buffer_hashed_at = Clock::get()?.unix_timestamp;
+        }
+        commit_migration(
+            &mut self.migrator,
+            &mut self.migration,
+            &self.executor.to_account_info(),
+            &self.proposer,
+        )
+    }
+}
 
-        Ok(())
+impl<'info> ApprovedAction<'info> {
+    /// Commit the result of a successful migration.
+    pub fn commit(&mut self) -> ProgramResult {
+        commit_migration(
+            &mut self.migrator,
+            &mut self.migration,
+            &self.executor.to_account_info(),
+            &self.proposer,
+        )
     }
 }
+
+/// Records the execution of a [Migration], returning the proposer's bond and paying the executor's bounty.
+fn commit_migration(
+    migrator: &mut Account<Migrator>,
+    migration: &mut Account<Migration>,
+    executor: &AccountInfo,
+    proposer: &AccountInfo,
+) -> ProgramResult {
+    migration.executed_at = Clock::get()?.unix_timestamp;
+    migration.executor = executor.key();
+    migration.funding_at_execution = migration.funding;
+
+    // return the proposer's bond
+    transfer_lamports(&migration.to_account_info(), proposer, migration.bond)?;
+    migration.bond = 0;
+
+    // pay the executor's bounty
+    transfer_lamports(&migration.to_account_info(), executor, migration.bounty)?;
+    migration.bounty = 0;
+
+    migrator.clear_pending_migration();
+    migrator.latest_migration_index = migration.index;
+
+    // ensure we still have enough lamports for rent exemption
+    let rent = Rent::get()?;
+    let migrator_info: AccountInfo = migrator.to_account_info();
+    require!(
+        rent.is_exempt(migrator_info.lamports(), migrator_info.data_len()),
+        InsufficientLamports
+    );
+
+    Ok(())
+}
@@ -0,0 +1,32 @@
+//! Helpers for resizing accounts.
+
+use anchor_lang::prelude::*;
+use solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use vipers::{invariant, unwrap_int};
+
+/// Resizes the data of an account owned by this program, zeroing any new bytes.
+///
+/// This version of `solana_program` does not expose `AccountInfo::realloc`, so the new
+/// length is written into the serialized input the same way later versions do.
+/// Can grow the account by at most [MAX_PERMITTED_DATA_INCREASE] bytes per instruction.
+pub fn realloc(info: &AccountInfo, new_len: usize) -> ProgramResult {
+    let old_len = info.data_len();
+    invariant!(
+        new_len <= unwrap_int!(old_len.checked_add(MAX_PERMITTED_DATA_INCREASE)),
+        "realloc exceeds the permitted data increase"
+    );
+
+    let mut data = info.try_borrow_mut_data()?;
+    let data_ptr = data.as_mut_ptr();
+    // SAFETY: the runtime serializes the length of the data immediately before it
+    // and reserves [MAX_PERMITTED_DATA_INCREASE] bytes after it.
+    #[allow(clippy::cast_ptr_alignment)]
+    unsafe {
+        *(data_ptr.offset(-8) as *mut u64) = new_len as u64;
+        *data = std::slice::from_raw_parts_mut(data_ptr, new_len);
+    }
+    if new_len > old_len {
+        data[old_len..].fill(0);
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+//! Resumable sha256, used to hash buffers across multiple transactions.
+
+use sha2::digest::generic_array::GenericArray;
+
+/// Initial sha256 state.
+pub const SHA256_IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// Size of a sha256 block in bytes.
+pub const BLOCK_LEN: usize = 64;
+
+/// Hashes all whole blocks of `data` into `state`, returning the number of bytes consumed.
+pub fn update(state: &mut [u32; 8], data: &[u8]) -> usize {
+    let mut consumed = 0;
+    for block in data.chunks_exact(BLOCK_LEN) {
+        sha2::compress256(state, std::slice::from_ref(GenericArray::from_slice(block)));
+        consumed += BLOCK_LEN;
+    }
+    consumed
+}
+
+/// Hashes the final partial block `tail` and returns the digest of the `total_len` bytes hashed.
+pub fn finalize(mut state: [u32; 8], tail: &[u8], total_len: u64) -> [u8; 32] {
+    let mut last = [0u8; BLOCK_LEN * 2];
+    last[..tail.len()].copy_from_slice(tail);
+    last[tail.len()] = 0x80;
+    // the length must fit after the padding byte
+    let padded_len = if tail.len() + 1 + 8 <= BLOCK_LEN {
+        BLOCK_LEN
+    } else {
+        BLOCK_LEN * 2
+    };
+    last[padded_len - 8..padded_len].copy_from_slice(&(total_len * 8).to_be_bytes());
+    update(&mut state, &last[..padded_len]);
+
+    let mut digest = [0u8; 32];
+    for (word, out) in state.iter().zip(digest.chunks_exact_mut(4)) {
+        out.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+
+    /// Hashes `data` in steps of `step` bytes, as [crate::migrator::hash_buffer] does.
+    fn resumable_hash(data: &[u8], step: usize) -> [u8; 32] {
+        let mut state = SHA256_IV;
+        let mut offset = 0;
+        loop {
+            let end = (offset + step).min(data.len());
+            offset += update(&mut state, &data[offset..end]);
+            if data.len() - offset < BLOCK_LEN {
+                return finalize(state, &data[offset..], data.len() as u64);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_sha256() {
+        for len in [0, 1, 55, 56, 63, 64, 65, 119, 120, 128, 1000] {
+            let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let expected: [u8; 32] = Sha256::digest(&data).into();
+            for step in [BLOCK_LEN, BLOCK_LEN * 3, 1024] {
+                assert_eq!(resumable_hash(&data, step), expected, "len {}", len);
+            }
+        }
+    }
+}
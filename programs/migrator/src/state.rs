@@ -2,6 +2,16 @@
 
 use anchor_lang::prelude::*;
 
+/// Maximum number of [Migrator::approvers].
+pub const MAX_APPROVERS: usize = 10;
+
+/// Number of [MigrationSummary]s stored in each [MigrationHistory] page.
+pub const HISTORY_PAGE_SIZE: usize = 32;
+
+/// Minimum number of seconds between proposing and executing an ejection of a [Migrator],
+/// used when [Migrator::min_delay] is shorter.
+pub const MIN_EJECT_DELAY: i64 = 60 * 60 * 24;
+
 /// Migrates programs.
 #[account]
 #[derive(Default)]
@@ -11,8 +21,11 @@ pub struct Migrator {
     /// Bump seed.
     pub bump: u8,
 
-    /// Authority which can approve migrations.
-    pub approver: Pubkey,
+    /// Set of accounts which can approve migrations.
+    pub approvers: Vec<Pubkey>,
+    /// Number of [Migrator::approvers] which must approve a [Migration]
+    /// before it may be executed.
+    pub threshold: u8,
     /// The current [Migration] that is approved for anyone to deploy.
     /// Only one [Migration] may be approved at a time.
     pub pending_migration: Pubkey,
@@ -20,20 +33,112 @@ pub struct Migrator {
     /// deployment/upgrade expires.
     /// If <= 0, there is considered to be no approved migration.
     pub approval_expires_at: i64,
+    /// Timestamp after which the [Migrator::pending_migration] may be executed.
+    /// -1 if there is no approved migration.
+    pub executable_after: i64,
+    /// Minimum number of seconds between a [Migration] being approved and it being executed.
+    pub min_delay: i64,
+
+    /// The approver proposed to replace [Migrator::replaced_approver].
+    /// [Pubkey::default] if there is no pending rotation.
+    pub proposed_approver: Pubkey,
+    /// The member of [Migrator::approvers] which will be replaced by [Migrator::proposed_approver].
+    pub replaced_approver: Pubkey,
+    /// When the current approver rotation was proposed.
+    pub rotation_proposed_at: i64,
+    /// Total number of [ApproverRotation]s that have taken place on this [Migrator].
+    pub num_rotations: u64,
+
+    /// Account which may veto the [Migrator::pending_migration] during its approval window.
+    /// The guardian cannot approve migrations.
+    /// [Pubkey::default] if there is no guardian.
+    pub guardian: Pubkey,
+    /// If true, no migrations may be proposed, approved, or executed.
+    pub paused: bool,
+
+    /// Number of lamports a proposer must escrow in order to propose a [Migration].
+    pub bond_amount: u64,
+    /// Account which receives the bonds of [Migration]s rejected as spam.
+    pub treasury: Pubkey,
+
+    /// Account which will become the upgrade authority of the program if the [Migrator] is ejected.
+    /// [Pubkey::default] if no ejection has been proposed.
+    pub eject_authority: Pubkey,
+    /// Timestamp after which the [Migrator] may be ejected. -1 if no ejection has been proposed.
+    pub eject_after: i64,
 
     /// Total number of migrations that have been proposed to this [Migrator].
     pub num_migrations: u64,
     /// Index of the latest migration to have taken place.
     pub latest_migration_index: u64,
+    /// Total number of migrations that have been archived into [MigrationHistory] pages.
+    pub num_archived: u64,
+    /// Number of [Migration] accounts which have not yet been withdrawn or archived.
+    /// The [Migrator] may only be ejected once this is zero.
+    pub num_open_migrations: u64,
 
     /// User-friendly name of the program.
     pub name: String,
     /// Description of the program.
     pub description: String,
+    /// Website of the program. Empty if unset.
+    pub website: String,
+    /// URL of the source repository of the program. Empty if unset.
+    pub repository: String,
+    /// URI of the logo of the program. Empty if unset.
+    pub logo_uri: String,
+}
+
+/// User-facing information about the program of a [Migrator].
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MigratorMetadata {
+    /// [Migrator::name].
+    pub name: String,
+    /// [Migrator::description].
+    pub description: String,
+    /// [Migrator::website].
+    pub website: String,
+    /// [Migrator::repository].
+    pub repository: String,
+    /// [Migrator::logo_uri].
+    pub logo_uri: String,
+}
+
+impl MigratorMetadata {
+    /// Number of bytes of string data in the metadata.
+    pub fn data_len(&self) -> usize {
+        self.name.len()
+            + self.description.len()
+            + self.website.len()
+            + self.repository.len()
+            + self.logo_uri.len()
+    }
+}
+
+/// What a [Migration] does to the program when executed.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MigrationKind {
+    /// Deploys or upgrades the program to the [Migration::buffer].
+    Upgrade,
+    /// Makes the program immutable by removing its upgrade authority.
+    Finalize,
+    /// Hands the upgrade authority of the program to `new_authority`,
+    /// which may be another [Migrator] or any other key.
+    TransferAuthority {
+        /// The new upgrade authority. Must co-sign the transfer.
+        new_authority: Pubkey,
+    },
+    /// Upgrades the program to a buffer which is only revealed when the [Migration] is executed.
+    /// Used for fixes that must not be published before they are deployed.
+    EmbargoedUpgrade {
+        /// The sha256 of the program in the buffer.
+        buffer_hash: [u8; 32],
+        /// The length of the program in the buffer.
+        buffer_len: u64,
+    },
 }
 
 #[account]
-#[derive(Default)]
 pub struct Migration {
     /// The [Pubkey] of the [Migrator].
     pub migrator: Pubkey,
@@ -41,25 +146,406 @@ pub struct Migration {
     pub index: u64,
     /// Bump seed.
     pub bump: u8,
+    /// What this [Migration] does.
+    pub kind: MigrationKind,
 
     /// The key of the buffer to migrate to.
     /// This must be set to the [Migrator].
+    /// [Pubkey::default] if the [Migration::kind] does not use a buffer.
     pub buffer: Pubkey,
     /// The [Pubkey] that proposed this [Migration].
     pub proposer: Pubkey,
+    /// Number of lamports escrowed in this account by the proposer.
+    /// Returned to the proposer on execution and forfeited if rejected as spam.
+    pub bond: u64,
+    /// Number of lamports escrowed in this account to be paid to the [Migration::executor].
+    pub bounty: u64,
+    /// Number of lamports escrowed in this account by contributors to pay for the migration,
+    /// not including deposits that have been reclaimed.
+    pub funding: u64,
+    /// [Migration::funding] at the time the migration was executed.
+    pub funding_at_execution: u64,
+    /// Number of lamports of [Migration::funding] spent on executing the migration.
+    pub funding_spent: u64,
+
+    /// Snapshot of [Migrator::approvers] at the time this [Migration] was proposed.
+    /// Only these accounts may approve this [Migration].
+    pub approvers: Vec<Pubkey>,
+    /// Snapshot of [Migrator::threshold] at the time this [Migration] was proposed.
+    pub threshold: u8,
+    /// Number of [MigrationApproval]s this [Migration] has received.
+    pub num_approvals: u8,
+    /// The earliest deadline given by any of the approvers. -1 if there are no approvals.
+    pub approval_deadline: i64,
 
     /// When the [Migration] was created.
     pub created_at: i64,
     /// If the [Migrator] rejected this [Migration], this is the timestamp when the migration was rejected.
     /// This also allows us to filter out spam.
     pub rejected_at: i64,
+    /// If the [Migrator::guardian] vetoed this [Migration], this is the timestamp when it was vetoed.
+    /// A vetoed [Migration] may never be approved again.
+    pub vetoed_at: i64,
     /// Timestamp of when this migration was executed. -1 if never executed.
     pub executed_at: i64,
     /// The [Pubkey] that executed this [Migration].
     pub executor: Pubkey,
+    /// When the [Migration::buffer] was handed back to the proposer. -1 if never.
+    /// Once its buffer is reclaimed, a [Migration] may never be approved or executed.
+    pub buffer_reclaimed_at: i64,
+
+    /// How the program in the [Migration::buffer] was built.
+    pub provenance: BuildProvenance,
+
+    /// Intermediate sha256 state of the program in the [Migration::buffer].
+    pub buffer_hash_state: [u32; 8],
+    /// Number of bytes of the program in the [Migration::buffer] hashed so far.
+    pub buffer_hash_offset: u64,
+    /// The sha256 of the program in the [Migration::buffer], computed on-chain.
+    /// Only valid once [Migration::buffer_hashed_at] is set.
+    pub buffer_hash: [u8; 32],
+    /// When the [Migration::buffer_hash] was computed. -1 if not yet computed.
+    pub buffer_hashed_at: i64,
+    /// The sha256 of the program that every [MigrationApproval] approved.
+    pub approved_buffer_hash: [u8; 32],
+    /// The length of the program that every [MigrationApproval] approved.
+    pub approved_buffer_len: u64,
+
+    /// If non-zero, the sha256 of a sealed [Migration::description],
+    /// which may only be revealed once the [Migration] has been executed.
+    pub description_hash: [u8; 32],
 
     /// Title describing the migration
     pub title: String,
     /// Description of the migration. It is recommended to use Markdown.
+    /// Empty until revealed if the description is sealed.
     pub description: String,
 }
+
+/// Structured information about how the program of a [Migration] was built,
+/// so that the build can be reproduced and checked.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BuildProvenance {
+    /// URL of the source repository.
+    pub repository: String,
+    /// Git commit hash of the source, hex-encoded.
+    pub commit: String,
+    /// Toolchain used for the build, e.g. the Anchor and Solana versions.
+    pub toolchain: String,
+    /// Digest of the verifiable build image, e.g. `sha256:...`.
+    pub image_digest: String,
+    /// Expected sha256 of the program ELF.
+    pub elf_sha256: [u8; 32],
+}
+
+impl BuildProvenance {
+    /// Number of bytes of string data in the provenance.
+    pub fn data_len(&self) -> usize {
+        self.repository.len() + self.commit.len() + self.toolchain.len() + self.image_digest.len()
+    }
+}
+
+/// An approval of a [Migration] by one of its approvers.
+#[account]
+#[derive(Default)]
+pub struct MigrationApproval {
+    /// The [Migration] being approved.
+    pub migration: Pubkey,
+    /// The approver which approved the [Migration].
+    pub approver: Pubkey,
+    /// Bump seed.
+    pub bump: u8,
+
+    /// When the approval was made.
+    pub approved_at: i64,
+    /// The timestamp that this approver's approval expires at.
+    pub deadline: i64,
+    /// The sha256 of the program in the buffer that was approved.
+    pub buffer_hash: [u8; 32],
+    /// The length of the program in the buffer that was approved.
+    pub buffer_len: u64,
+}
+
+/// Checks that a set of approvers and its threshold are valid.
+pub fn validate_approver_set(approvers: &[Pubkey], threshold: u8) -> ProgramResult {
+    require!(
+        !approvers.is_empty() && approvers.len() <= MAX_APPROVERS,
+        InvalidApproverSet
+    );
+    for (i, approver) in approvers.iter().enumerate() {
+        require!(!approvers[..i].contains(approver), InvalidApproverSet);
+    }
+    require!(
+        threshold > 0 && threshold as usize <= approvers.len(),
+        InvalidThreshold
+    );
+    Ok(())
+}
+
+/// A record of a member of [Migrator::approvers] being replaced.
+#[account]
+#[derive(Default)]
+pub struct ApproverRotation {
+    /// The [Migrator].
+    pub migrator: Pubkey,
+    /// The unique index of the [ApproverRotation].
+    pub index: u64,
+    /// Bump seed.
+    pub bump: u8,
+
+    /// The approver that was removed.
+    pub old_approver: Pubkey,
+    /// The approver that was added.
+    pub new_approver: Pubkey,
+
+    /// When the rotation was proposed.
+    pub proposed_at: i64,
+    /// When the new approver accepted the rotation.
+    pub accepted_at: i64,
+}
+
+/// Records who reserved a program ID via [crate::migrator::reserve_program_id].
+#[account]
+#[derive(Default)]
+pub struct Reservation {
+    /// The reserved program ID.
+    pub program: Pubkey,
+    /// Bump seed.
+    pub bump: u8,
+
+    /// The account which reserved the program ID.
+    pub reserver: Pubkey,
+    /// An approver designated by the reserver who may also create the [Migrator].
+    /// [Pubkey::default] if none.
+    pub approver: Pubkey,
+    /// The account which paid for the reservation.
+    pub payer: Pubkey,
+    /// When the program ID was reserved.
+    pub reserved_at: i64,
+}
+
+impl Reservation {
+    /// Returns true if `key` may create the [Migrator] of the reserved program.
+    pub fn is_authorized(&self, key: Pubkey) -> bool {
+        key == self.reserver || (self.approver != Pubkey::default() && key == self.approver)
+    }
+}
+
+/// A contributor's deposit towards the [Migration::funding] of a [Migration].
+#[account]
+#[derive(Default)]
+pub struct MigrationDeposit {
+    /// The [Migration] being funded.
+    pub migration: Pubkey,
+    /// The account which contributed the deposit.
+    pub contributor: Pubkey,
+    /// Bump seed.
+    pub bump: u8,
+
+    /// Number of lamports deposited.
+    pub amount: u64,
+}
+
+/// A page of the append-only history of archived [Migration]s of a [Migrator].
+#[account]
+#[derive(Default)]
+pub struct MigrationHistory {
+    /// The [Migrator].
+    pub migrator: Pubkey,
+    /// The index of this page.
+    pub page: u64,
+    /// Bump seed.
+    pub bump: u8,
+
+    /// Summaries of archived [Migration]s, in the order they were archived.
+    /// Holds at most [HISTORY_PAGE_SIZE] entries.
+    pub entries: Vec<MigrationSummary>,
+}
+
+/// How a [Migration] ended.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MigrationOutcome {
+    /// The [Migration] was executed.
+    Executed,
+    /// The [Migration] was rejected by the approvers.
+    Rejected,
+    /// The [Migration] was vetoed by the guardian.
+    Vetoed,
+    /// The approval of the [Migration] expired before it was executed.
+    Expired,
+}
+
+/// A fixed-size summary of an archived [Migration].
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MigrationSummary {
+    /// [Migration::index].
+    pub index: u64,
+    /// [Migration::kind].
+    pub kind: MigrationKind,
+    /// [Migration::buffer].
+    pub buffer: Pubkey,
+    /// [Migration::proposer].
+    pub proposer: Pubkey,
+    /// [Migration::executor].
+    pub executor: Pubkey,
+    /// [Migration::created_at].
+    pub created_at: i64,
+    /// [Migration::rejected_at].
+    pub rejected_at: i64,
+    /// [Migration::vetoed_at].
+    pub vetoed_at: i64,
+    /// [Migration::executed_at].
+    pub executed_at: i64,
+    /// How the [Migration] ended.
+    pub outcome: MigrationOutcome,
+}
+
+impl Migrator {
+    /// Number of bytes required to store a [Migrator] with the given approvers and metadata.
+    pub fn space(num_approvers: usize, metadata: &MigratorMetadata) -> usize {
+        8 + std::mem::size_of::<Migrator>()
+            + num_approvers * std::mem::size_of::<Pubkey>()
+            + metadata.data_len()
+    }
+
+    /// Returns the [MigratorMetadata].
+    pub fn metadata(&self) -> MigratorMetadata {
+        MigratorMetadata {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            website: self.website.clone(),
+            repository: self.repository.clone(),
+            logo_uri: self.logo_uri.clone(),
+        }
+    }
+
+    /// Replaces the [MigratorMetadata].
+    pub fn set_metadata(&mut self, metadata: MigratorMetadata) {
+        self.name = metadata.name;
+        self.description = metadata.description;
+        self.website = metadata.website;
+        self.repository = metadata.repository;
+        self.logo_uri = metadata.logo_uri;
+    }
+
+    /// Returns true if the given key is one of the [Migrator::approvers].
+    pub fn is_approver(&self, key: &Pubkey) -> bool {
+        self.approvers.contains(key)
+    }
+
+    /// Index of the [MigrationHistory] page that the next archived [Migration] is written to.
+    pub fn current_history_page(&self) -> u64 {
+        self.num_archived / HISTORY_PAGE_SIZE as u64
+    }
+
+    /// Returns true if `migration` is the [Migrator::pending_migration]
+    /// and its approval has not yet expired.
+    pub fn is_pending(&self, migration: Pubkey, now: i64) -> bool {
+        self.pending_migration == migration && now < self.approval_expires_at
+    }
+
+    /// Cancels the [Migrator::pending_migration], if any.
+    pub fn clear_pending_migration(&mut self) {
+        self.pending_migration = Pubkey::default();
+        self.approval_expires_at = -1;
+        self.executable_after = -1;
+    }
+
+    /// Asserts that `approver` is one of the [Migrator::approvers] and that, together
+    /// with the `co_signers`, a quorum of the approvers signed the transaction.
+    pub fn assert_quorum_signed(
+        &self,
+        approver: &AccountInfo,
+        co_signers: &[AccountInfo],
+    ) -> ProgramResult {
+        require!(self.is_approver(approver.key), UnauthorizedApprover);
+        self.assert_approver_quorum(approver, co_signers)
+    }
+
+    /// Asserts that at least [Migrator::threshold] of the [Migrator::approvers]
+    /// are among the `approver` and `co_signers` and have signed the transaction.
+    ///
+    /// Instructions requiring a quorum are signed by one approver, and the additional
+    /// approvers required to reach the quorum are passed as signers in the remaining accounts.
+    pub fn assert_approver_quorum(
+        &self,
+        approver: &AccountInfo,
+        co_signers: &[AccountInfo],
+    ) -> ProgramResult {
+        let has_signed = |key: &Pubkey, info: &AccountInfo| info.is_signer && info.key == key;
+        let num_signed = self
+            .approvers
+            .iter()
+            .filter(|key| {
+                has_signed(key, approver) || co_signers.iter().any(|info| has_signed(key, info))
+            })
+            .count();
+        require!(num_signed >= self.threshold as usize, InsufficientApprovers);
+        Ok(())
+    }
+}
+
+impl Migration {
+    /// Returns true if the program in the [Migration::buffer] was hashed and
+    /// matches what the approvers approved.
+    pub fn buffer_matches_approval(&self) -> bool {
+        self.buffer_hashed_at != -1
+            && self.buffer_hash == self.approved_buffer_hash
+            && self.buffer_hash_offset == self.approved_buffer_len
+    }
+
+    /// Returns true if the [Migration::description] is sealed and has not yet been revealed.
+    pub fn is_description_sealed(&self) -> bool {
+        self.description_hash != [0; 32] && self.description.is_empty()
+    }
+
+    /// Returns true if this [Migration] deploys a buffer.
+    pub fn has_buffer(&self) -> bool {
+        self.kind == MigrationKind::Upgrade
+    }
+
+    /// Returns the number of lamports of a deposit of `amount` that may be refunded.
+    /// After execution, the deposit is charged its share of [Migration::funding_spent].
+    pub fn refundable_amount(&self, amount: u64) -> Option<u64> {
+        if self.executed_at == -1 || self.funding_spent == 0 {
+            return Some(amount);
+        }
+        // round the share of spent funding up so refunds never exceed the surplus
+        let total = self.funding_at_execution as u128;
+        let share = (amount as u128)
+            .checked_mul(self.funding_spent as u128)?
+            .checked_add(total.checked_sub(1)?)?
+            .checked_div(total)?;
+        amount.checked_sub(share as u64)
+    }
+
+    /// Returns true if the given key is one of the [Migration::approvers].
+    pub fn is_approver(&self, key: &Pubkey) -> bool {
+        self.approvers.contains(key)
+    }
+
+    /// Returns true if enough approvals have been made for this [Migration] to be executed.
+    pub fn has_quorum(&self) -> bool {
+        self.threshold > 0 && self.num_approvals >= self.threshold
+    }
+
+    /// Returns how this [Migration] ended, if it has.
+    pub fn outcome(&self, now: i64) -> Option<MigrationOutcome> {
+        if self.executed_at != -1 {
+            Some(MigrationOutcome::Executed)
+        } else if self.vetoed_at != -1 {
+            Some(MigrationOutcome::Vetoed)
+        } else if self.rejected_at != -1 {
+            Some(MigrationOutcome::Rejected)
+        } else if self.is_expired(now) {
+            Some(MigrationOutcome::Expired)
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if this [Migration] was approved but its approval has expired.
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.has_quorum() && now >= self.approval_deadline
+    }
+}
@@ -0,0 +1,327 @@
+//! Helpers for running instructions natively in tests.
+
+use crate::state::*;
+use anchor_lang::prelude::*;
+use anchor_lang::InstructionData;
+use solana_program::{
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    entrypoint::{MAX_PERMITTED_DATA_INCREASE, SUCCESS},
+    program_stubs,
+    system_instruction::SystemInstruction,
+    system_program, sysvar,
+};
+use std::sync::Once;
+
+/// Timestamp returned by the [Clock] sysvar in tests.
+pub const NOW: i64 = 1_640_000_000;
+
+static INSTALL_STUBS: Once = Once::new();
+
+/// Syscall stubs providing sysvars and the parts of the system and loader programs
+/// used by the migrator. Cross-program invocations check that all required signers signed.
+///
+/// Account owners cannot be changed natively, so accounts initialized by an instruction
+/// must already be owned by this program; see [uninitialized].
+struct TestSyscallStubs;
+
+impl program_stubs::SyscallStubs for TestSyscallStubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            unix_timestamp: NOW,
+            ..Clock::default()
+        };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &solana_program::instruction::Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let find = |key: &Pubkey| {
+            account_infos
+                .iter()
+                .find(|info| info.key == key)
+                .expect("missing account")
+        };
+        let signers = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &crate::ID).unwrap())
+            .collect::<Vec<_>>();
+        for meta in instruction.accounts.iter().filter(|meta| meta.is_signer) {
+            assert!(
+                find(&meta.pubkey).is_signer || signers.contains(&meta.pubkey),
+                "missing signature of {}",
+                meta.pubkey
+            );
+        }
+
+        if instruction.program_id == bpf_loader_upgradeable::ID {
+            // only SetAuthority and SetAuthorityChecked are invoked on deployed programs
+            let variant: u32 = bincode::deserialize(&instruction.data).unwrap();
+            assert!(
+                variant == 4 || variant == 7,
+                "unsupported loader instruction"
+            );
+            let authority = instruction.accounts[1].pubkey;
+            let new_authority = instruction.accounts.get(2).map(|meta| meta.pubkey);
+            let program_data = find(&instruction.accounts[0].pubkey);
+            let mut data = program_data.try_borrow_mut_data()?;
+            let slot = match bincode::deserialize(&data).unwrap() {
+                UpgradeableLoaderState::ProgramData {
+                    slot,
+                    upgrade_authority_address,
+                } => {
+                    assert_eq!(upgrade_authority_address, Some(authority));
+                    slot
+                }
+                other => panic!("not program data: {:?}", other),
+            };
+            let state = UpgradeableLoaderState::ProgramData {
+                slot,
+                upgrade_authority_address: new_authority,
+            };
+            bincode::serialize_into(&mut data[..], &state).unwrap();
+            return Ok(());
+        }
+
+        assert_eq!(instruction.program_id, system_program::ID);
+        let debit = |from: &AccountInfo, to: &AccountInfo, lamports: u64| {
+            **from.try_borrow_mut_lamports().unwrap() -= lamports;
+            **to.try_borrow_mut_lamports().unwrap() += lamports;
+        };
+        let from = find(&instruction.accounts[0].pubkey);
+        match bincode::deserialize(&instruction.data).unwrap() {
+            SystemInstruction::Transfer { lamports } => {
+                debit(from, find(&instruction.accounts[1].pubkey), lamports);
+            }
+            SystemInstruction::Allocate { space } => {
+                assert!(from.data_is_empty(), "account already allocated");
+                crate::realloc::realloc(from, space as usize)?;
+            }
+            SystemInstruction::Assign { owner } => {
+                assert_eq!(
+                    *from.owner, owner,
+                    "accounts must be created with their owner"
+                );
+            }
+            other => panic!("unsupported system instruction: {:?}", other),
+        }
+        Ok(())
+    }
+}
+
+/// Leaks `data` laid out as the runtime serializes it: preceded by its length
+/// and followed by [MAX_PERMITTED_DATA_INCREASE] bytes of spare space.
+fn leak_data(data: Vec<u8>) -> &'static mut [u8] {
+    let len = data.len();
+    // allocate words so that the length prefix is aligned
+    let words = (8 + len + MAX_PERMITTED_DATA_INCREASE).div_ceil(8);
+    let buf: &'static mut [u64] = Box::leak(vec![0u64; words].into_boxed_slice());
+    buf[0] = len as u64;
+    let bytes = unsafe { std::slice::from_raw_parts_mut(buf.as_mut_ptr() as *mut u8, words * 8) };
+    bytes[8..8 + len].copy_from_slice(&data);
+    &mut bytes[8..8 + len]
+}
+
+/// Creates an [AccountInfo] which lives for the rest of the test.
+pub fn account_info(
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+    is_signer: bool,
+    is_writable: bool,
+) -> AccountInfo<'static> {
+    AccountInfo::new(
+        Box::leak(Box::new(key)),
+        is_signer,
+        is_writable,
+        Box::leak(Box::new(lamports)),
+        leak_data(data),
+        Box::leak(Box::new(owner)),
+        false,
+        0,
+    )
+}
+
+/// Creates a writable account owned by this program holding `account`.
+pub fn program_account<T: AccountSerialize>(key: Pubkey, account: &T) -> AccountInfo<'static> {
+    program_account_with_space(key, account, 0)
+}
+
+/// Creates a writable account owned by this program holding `account`,
+/// with `extra` bytes of space left over.
+pub fn program_account_with_space<T: AccountSerialize>(
+    key: Pubkey,
+    account: &T,
+    extra: usize,
+) -> AccountInfo<'static> {
+    let mut data = vec![];
+    account.try_serialize(&mut data).unwrap();
+    data.resize(data.len() + extra, 0);
+    account_info(key, crate::ID, 1_000_000_000, data, false, true)
+}
+
+/// Creates a system account with lamports, which may sign.
+pub fn wallet(key: Pubkey) -> AccountInfo<'static> {
+    account_info(key, system_program::ID, 1_000_000_000, vec![], true, true)
+}
+
+/// Creates an empty account to be initialized by an instruction.
+///
+/// It is already owned by this program and holds a lamport, so Anchor allocates and
+/// assigns it rather than creating it.
+pub fn uninitialized(key: Pubkey) -> AccountInfo<'static> {
+    account_info(key, crate::ID, 1, vec![], false, true)
+}
+
+/// Creates an account which has never been created.
+pub fn nonexistent(key: Pubkey) -> AccountInfo<'static> {
+    account_info(key, system_program::ID, 0, vec![], false, false)
+}
+
+/// Creates an executable program account.
+fn program_id_account(key: Pubkey) -> AccountInfo<'static> {
+    let mut info = account_info(key, Pubkey::default(), 1, vec![], false, false);
+    info.executable = true;
+    info
+}
+
+/// The [System] program account.
+pub fn system_program_account() -> AccountInfo<'static> {
+    program_id_account(system_program::ID)
+}
+
+/// The [bpf_loader_upgradeable] program account.
+pub fn bpf_loader_upgradeable_account() -> AccountInfo<'static> {
+    program_id_account(bpf_loader_upgradeable::ID)
+}
+
+/// The [Rent] and [Clock] sysvar accounts.
+pub fn rent_and_clock() -> [AccountInfo<'static>; 2] {
+    let clock = Clock {
+        unix_timestamp: NOW,
+        ..Clock::default()
+    };
+    let sysvar_account = |key, data| account_info(key, sysvar::ID, 1, data, false, false);
+    [
+        sysvar_account(
+            sysvar::rent::ID,
+            bincode::serialize(&Rent::default()).unwrap(),
+        ),
+        sysvar_account(sysvar::clock::ID, bincode::serialize(&clock).unwrap()),
+    ]
+}
+
+/// Creates a writable [bpf_loader_upgradeable] account in `state`, followed by `data`.
+pub fn loader_account(
+    key: Pubkey,
+    state: &UpgradeableLoaderState,
+    data: &[u8],
+) -> AccountInfo<'static> {
+    let mut account_data = bincode::serialize(state).unwrap();
+    account_data.extend_from_slice(data);
+    account_info(
+        key,
+        bpf_loader_upgradeable::ID,
+        1_000_000,
+        account_data,
+        false,
+        true,
+    )
+}
+
+/// Creates a buffer holding `program`, whose authority is `authority`.
+pub fn buffer_account(authority: Pubkey, program: &[u8]) -> AccountInfo<'static> {
+    loader_account(
+        Pubkey::new_unique(),
+        &UpgradeableLoaderState::Buffer {
+            authority_address: Some(authority),
+        },
+        program,
+    )
+}
+
+/// Creates the program and program data accounts of a deployed program
+/// whose upgrade authority is `authority`.
+pub fn live_program(program_key: Pubkey, authority: Pubkey) -> [AccountInfo<'static>; 2] {
+    let (programdata_address, _) =
+        Pubkey::find_program_address(&[program_key.as_ref()], &bpf_loader_upgradeable::ID);
+    [
+        loader_account(
+            program_key,
+            &UpgradeableLoaderState::Program {
+                programdata_address,
+            },
+            &[],
+        ),
+        loader_account(
+            programdata_address,
+            &UpgradeableLoaderState::ProgramData {
+                slot: 0,
+                upgrade_authority_address: Some(authority),
+            },
+            &[],
+        ),
+    ]
+}
+
+/// Runs an instruction of this program natively.
+pub fn process<I: InstructionData>(accounts: &[AccountInfo], ix: I) -> ProgramResult {
+    INSTALL_STUBS.call_once(|| {
+        program_stubs::set_syscall_stubs(Box::new(TestSyscallStubs));
+    });
+    crate::entry(&crate::ID, accounts, &ix.data())
+}
+
+/// Deserializes an account of this program.
+pub fn load<T: AccountDeserialize>(info: &AccountInfo) -> T {
+    T::try_deserialize(&mut &info.try_borrow_data().unwrap()[..]).unwrap()
+}
+
+/// A freshly proposed [MigrationKind::Upgrade] [Migration].
+pub fn new_migration(migrator: &Migrator, migrator_key: Pubkey) -> Migration {
+    Migration {
+        migrator: migrator_key,
+        index: 0,
+        bump: 0,
+        kind: MigrationKind::Upgrade,
+        buffer: Pubkey::new_unique(),
+        proposer: Pubkey::new_unique(),
+        bond: 0,
+        bounty: 0,
+        funding: 0,
+        funding_at_execution: 0,
+        funding_spent: 0,
+        approvers: migrator.approvers.clone(),
+        threshold: migrator.threshold,
+        num_approvals: 0,
+        approval_deadline: -1,
+        created_at: NOW,
+        rejected_at: -1,
+        vetoed_at: -1,
+        executed_at: -1,
+        executor: Pubkey::default(),
+        buffer_reclaimed_at: -1,
+        provenance: BuildProvenance::default(),
+        buffer_hash_state: crate::sha256::SHA256_IV,
+        buffer_hash_offset: 0,
+        buffer_hash: [0; 32],
+        buffer_hashed_at: -1,
+        approved_buffer_hash: [0; 32],
+        approved_buffer_len: 0,
+        description_hash: [0; 32],
+        title: "Upgrade".to_string(),
+        description: String::new(),
+    }
+}
@@ -1,4 +1,5 @@
 import { utils } from "@project-serum/anchor";
+import type { u64 } from "@saberhq/token-utils";
 import { PublicKey } from "@solana/web3.js";
 
 import { PROGRAM_ID } from "./constants";
@@ -11,3 +12,68 @@ export const findMigratorKey = async (
     PROGRAM_ID
   );
 };
+
+export const findReservationKey = async (
+  programID: PublicKey
+): Promise<[PublicKey, number]> => {
+  return await PublicKey.findProgramAddress(
+    [utils.bytes.utf8.encode("reservation"), programID.toBytes()],
+    PROGRAM_ID
+  );
+};
+
+export const findMigrationApprovalKey = async (
+  migration: PublicKey,
+  approver: PublicKey
+): Promise<[PublicKey, number]> => {
+  return await PublicKey.findProgramAddress(
+    [
+      utils.bytes.utf8.encode("approval"),
+      migration.toBytes(),
+      approver.toBytes(),
+    ],
+    PROGRAM_ID
+  );
+};
+
+export const findApproverRotationKey = async (
+  migrator: PublicKey,
+  index: u64
+): Promise<[PublicKey, number]> => {
+  return await PublicKey.findProgramAddress(
+    [
+      utils.bytes.utf8.encode("rotation"),
+      migrator.toBytes(),
+      index.toArrayLike(Buffer, "le", 8),
+    ],
+    PROGRAM_ID
+  );
+};
+
+export const findMigrationDepositKey = async (
+  migration: PublicKey,
+  contributor: PublicKey
+): Promise<[PublicKey, number]> => {
+  return await PublicKey.findProgramAddress(
+    [
+      utils.bytes.utf8.encode("deposit"),
+      migration.toBytes(),
+      contributor.toBytes(),
+    ],
+    PROGRAM_ID
+  );
+};
+
+export const findMigrationHistoryKey = async (
+  migrator: PublicKey,
+  page: u64
+): Promise<[PublicKey, number]> => {
+  return await PublicKey.findProgramAddress(
+    [
+      utils.bytes.utf8.encode("history"),
+      migrator.toBytes(),
+      page.toArrayLike(Buffer, "le", 8),
+    ],
+    PROGRAM_ID
+  );
+};